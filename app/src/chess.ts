@@ -45,9 +45,19 @@ export enum GameState {
     Stalemate = "statemate",
     Checkmate = "checkmate",
     InsufficientMaterials = "insufficient materials",
-    ThreefoldRepetition = "threefold repetition",
 }
 
+export type TOutcome = {
+    winner?: TColor;
+    termination: string;
+};
+
+const TERMINATIONS: Record<string, GameState> = {
+    checkmate: GameState.Checkmate,
+    stalemate: GameState.Stalemate,
+    "insufficient material": GameState.InsufficientMaterials,
+};
+
 export class Piece {
     readonly type: string;
     readonly color: TColor;
@@ -147,14 +157,9 @@ export function playMove(m: TMove) {
 
     let state = GameState.InProgress;
 
-    if (chess.is_checkmate()) {
-        state = GameState.Checkmate;
-    } else if (chess.is_stalemate()) {
-        state = GameState.Stalemate;
-    } else if (chess.is_insufficient_materials()) {
-        state = GameState.InsufficientMaterials;
-    } else if (chess.is_threefold_repetition()) {
-        state = GameState.ThreefoldRepetition;
+    const outcome: TOutcome | undefined = chess.outcome();
+    if (outcome) {
+        state = TERMINATIONS[outcome.termination] ?? GameState.Draw;
     }
 
     if (state != GameState.InProgress) {
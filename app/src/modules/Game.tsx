@@ -65,9 +65,9 @@ export default function Game({ onOpponentMove, makeMove, leaveGame }: Props) {
                 setModal(true);
             }
             if (
+                state === GameState.Draw ||
                 state === GameState.Stalemate ||
-                state === GameState.InsufficientMaterials ||
-                state === GameState.ThreefoldRepetition
+                state === GameState.InsufficientMaterials
             ) {
                 console.log("draw by " + state);
                 setGameStatus({
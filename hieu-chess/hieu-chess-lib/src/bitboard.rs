@@ -0,0 +1,279 @@
+use crate::board::SIZE_0X88;
+use crate::chess::Color;
+use crate::piece::{PType, Piece};
+use crate::square::Square;
+
+// https://www.chessprogramming.org/Bitboards
+// one u64 per piece type and one per color, bit (rank * 8 + file) is set when a piece stands on that square.
+// the 0x88 array is kept next to the bitboards so `get` stays a lookup, and the wasm side can still
+// read the board straight out of memory
+#[derive(Clone)]
+pub struct Board {
+    _board: [Option<Piece>; SIZE_0X88],
+    pieces: [u64; 6],
+    colors: [u64; 2],
+}
+
+const ARRAY_REPEAT_VALUE: Option<Piece> = None;
+
+impl Board {
+    pub fn new() -> Self {
+        Self {
+            _board: [ARRAY_REPEAT_VALUE; SIZE_0X88],
+            pieces: [0; 6],
+            colors: [0; 2],
+        }
+    }
+
+    pub fn set(&mut self, piece: Piece, idx: &u8) {
+        self.remove(idx);
+
+        let bit = 1 << index(Square(*idx));
+        self.pieces[piece_index(piece.p_type)] |= bit;
+        self.colors[color_index(piece.color)] |= bit;
+        self._board[*idx as usize] = Some(piece);
+    }
+
+    pub fn remove(&mut self, idx: &u8) {
+        let Some(piece) = self._board[*idx as usize].take() else {
+            return;
+        };
+
+        let bit = 1 << index(Square(*idx));
+        self.pieces[piece_index(piece.p_type)] &= !bit;
+        self.colors[color_index(piece.color)] &= !bit;
+    }
+
+    pub fn get(&self, idx: &u8) -> Option<&Piece> {
+        self._board[*idx as usize].as_ref()
+    }
+
+    pub fn get_board_ptr(&self) -> *const Option<Piece> {
+        self._board.as_ptr()
+    }
+
+    pub fn pieces(&self, p_type: PType, color: Color) -> u64 {
+        self.pieces[piece_index(p_type)] & self.colors[color_index(color)]
+    }
+
+    pub fn color(&self, color: Color) -> u64 {
+        self.colors[color_index(color)]
+    }
+
+    pub fn occupied(&self) -> u64 {
+        self.colors[0] | self.colors[1]
+    }
+}
+
+// piece types are single bits, so the bit position doubles as an index
+fn piece_index(p_type: PType) -> usize {
+    (p_type as u8).trailing_zeros() as usize
+}
+
+fn color_index(color: Color) -> usize {
+    match color {
+        Color::WHITE => 0,
+        Color::BLACK => 1,
+    }
+}
+
+pub fn index(sq: Square) -> usize {
+    (sq.rank() * 8 + sq.file()) as usize
+}
+
+pub fn square(idx: usize) -> Square {
+    Square(((idx / 8) * 16 + idx % 8) as u8)
+}
+
+// iterates over the squares of the set bits, lowest first
+pub fn squares(mut bb: u64) -> impl Iterator<Item = Square> {
+    std::iter::from_fn(move || {
+        if bb == 0 {
+            return None;
+        }
+
+        let idx = bb.trailing_zeros() as usize;
+        bb &= bb - 1;
+        Some(square(idx))
+    })
+}
+
+// (rank, file) offsets
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (2, 1),
+    (1, 2),
+    (-1, 2),
+    (-2, 1),
+    (-2, -1),
+    (-1, -2),
+    (1, -2),
+    (2, -1),
+];
+const KING_OFFSETS: [(i8, i8); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+const KNIGHT_ATTACKS: [u64; 64] = step_attacks(&KNIGHT_OFFSETS);
+const KING_ATTACKS: [u64; 64] = step_attacks(&KING_OFFSETS);
+const PAWN_ATTACKS: [[u64; 64]; 2] = [
+    step_attacks(&[(1, -1), (1, 1)]),
+    step_attacks(&[(-1, -1), (-1, 1)]),
+];
+
+// the first four directions go towards higher squares, the last four towards lower ones
+const RAYS: [[u64; 64]; 8] = [
+    rays(1, 0),
+    rays(0, 1),
+    rays(1, 1),
+    rays(1, -1),
+    rays(-1, 0),
+    rays(0, -1),
+    rays(-1, -1),
+    rays(-1, 1),
+];
+const ROOK_DIRECTIONS: [usize; 4] = [0, 1, 4, 5];
+const BISHOP_DIRECTIONS: [usize; 4] = [2, 3, 6, 7];
+
+const fn step_attacks(offsets: &[(i8, i8)]) -> [u64; 64] {
+    let mut table = [0; 64];
+    let mut sq = 0;
+
+    while sq < 64 {
+        let mut i = 0;
+
+        while i < offsets.len() {
+            let rank = (sq / 8) as i8 + offsets[i].0;
+            let file = (sq % 8) as i8 + offsets[i].1;
+
+            if rank >= 0 && rank < 8 && file >= 0 && file < 8 {
+                table[sq] |= 1 << (rank * 8 + file) as u32;
+            }
+            i += 1;
+        }
+        sq += 1;
+    }
+
+    table
+}
+
+const fn rays(rank_step: i8, file_step: i8) -> [u64; 64] {
+    let mut table = [0; 64];
+    let mut sq = 0;
+
+    while sq < 64 {
+        let mut rank = (sq / 8) as i8 + rank_step;
+        let mut file = (sq % 8) as i8 + file_step;
+
+        while rank >= 0 && rank < 8 && file >= 0 && file < 8 {
+            table[sq] |= 1 << (rank * 8 + file) as u32;
+            rank += rank_step;
+            file += file_step;
+        }
+        sq += 1;
+    }
+
+    table
+}
+
+// https://www.chessprogramming.org/Classical_Approach
+// the ray stops at the first blocker, which is the nearest set bit in the direction of the ray
+fn ray_attacks(direction: usize, idx: usize, occupied: u64) -> u64 {
+    let ray = RAYS[direction][idx];
+    let blockers = ray & occupied;
+
+    if blockers == 0 {
+        return ray;
+    }
+
+    let blocker = if direction < 4 {
+        blockers.trailing_zeros()
+    } else {
+        63 - blockers.leading_zeros()
+    };
+
+    ray ^ RAYS[direction][blocker as usize]
+}
+
+pub fn knight_attacks(idx: usize) -> u64 {
+    KNIGHT_ATTACKS[idx]
+}
+
+pub fn king_attacks(idx: usize) -> u64 {
+    KING_ATTACKS[idx]
+}
+
+// the squares a pawn of `color` standing on `idx` attacks
+pub fn pawn_attacks(color: Color, idx: usize) -> u64 {
+    PAWN_ATTACKS[color_index(color)][idx]
+}
+
+pub fn rook_attacks(idx: usize, occupied: u64) -> u64 {
+    ROOK_DIRECTIONS
+        .iter()
+        .fold(0, |attacks, d| attacks | ray_attacks(*d, idx, occupied))
+}
+
+pub fn bishop_attacks(idx: usize, occupied: u64) -> u64 {
+    BISHOP_DIRECTIONS
+        .iter()
+        .fold(0, |attacks, d| attacks | ray_attacks(*d, idx, occupied))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bb(squares: &[&str]) -> u64 {
+        squares
+            .iter()
+            .fold(0, |bb, sq| bb | 1 << index(Square::try_from(*sq).unwrap()))
+    }
+
+    #[test]
+    fn board_bitboards() {
+        let mut board = Board::new();
+        let e4 = Square::try_from("e4").unwrap();
+
+        board.set(Piece::new(PType::KNIGHT, Color::WHITE), &e4);
+        board.set(Piece::new(PType::QUEEN, Color::BLACK), &e4);
+
+        assert_eq!(board.pieces(PType::KNIGHT, Color::WHITE), 0);
+        assert_eq!(board.pieces(PType::QUEEN, Color::BLACK), bb(&["e4"]));
+        assert_eq!(board.color(Color::WHITE), 0);
+
+        board.remove(&e4);
+        assert_eq!(board.occupied(), 0);
+        assert!(board.get(&e4).is_none());
+    }
+
+    #[test]
+    fn attacks() {
+        let idx = |sq: &str| index(Square::try_from(sq).unwrap());
+
+        assert_eq!(knight_attacks(idx("a1")), bb(&["b3", "c2"]));
+        assert_eq!(king_attacks(idx("h8")), bb(&["g8", "g7", "h7"]));
+        assert_eq!(pawn_attacks(Color::WHITE, idx("a2")), bb(&["b3"]));
+        assert_eq!(pawn_attacks(Color::BLACK, idx("e7")), bb(&["d6", "f6"]));
+
+        let occupied = bb(&["c3", "c6", "f3", "b2"]);
+        assert_eq!(
+            rook_attacks(idx("c3"), occupied),
+            bb(&["c4", "c5", "c6", "c2", "c1", "d3", "e3", "f3", "b3", "a3"])
+        );
+        assert_eq!(
+            bishop_attacks(idx("c3"), occupied),
+            bb(&["d4", "e5", "f6", "g7", "h8", "b4", "a5", "d2", "e1", "b2"])
+        );
+        assert_eq!(
+            squares(bb(&["h8", "a1", "e4"])).collect::<Vec<_>>(),
+            ["a1", "e4", "h8"].map(|sq| Square::try_from(sq).unwrap())
+        );
+    }
+}
@@ -1,14 +1,22 @@
+#[cfg(not(feature = "bitboard"))]
 use crate::piece::Piece;
 
 pub const SIZE_0X88: usize = 128;
 
+// the bitboard backend keeps the same interface, so the rest of the crate doesn't care which one is used
+#[cfg(feature = "bitboard")]
+pub use crate::bitboard::Board;
+
+#[cfg(not(feature = "bitboard"))]
 #[derive(Clone)]
 pub struct Board {
     _board: [Option<Piece>; SIZE_0X88],
 }
 
+#[cfg(not(feature = "bitboard"))]
 const ARRAY_REPEAT_VALUE: Option<Piece> = None;
 
+#[cfg(not(feature = "bitboard"))]
 impl Board {
     pub fn new() -> Self {
         Self {
@@ -25,19 +33,7 @@ impl Board {
     }
 
     pub fn get(&self, idx: &u8) -> Option<&Piece> {
-        return self._board[*idx as usize].as_ref();
-    }
-
-    pub fn get_mut(&mut self, idx: &u8) -> Option<&mut Piece> {
-        return self._board[*idx as usize].as_mut();
-    }
-
-    pub fn clear(&mut self) {
-        self._board = [ARRAY_REPEAT_VALUE; SIZE_0X88];
-    }
-
-    pub fn get_internal_board(&self) -> &[Option<Piece>; SIZE_0X88] {
-        &self._board
+        self._board[*idx as usize].as_ref()
     }
 
     pub fn get_board_ptr(&self) -> *const Option<Piece> {
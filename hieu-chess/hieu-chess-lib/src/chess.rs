@@ -1,21 +1,39 @@
 use crate::board::{Board, SIZE_0X88};
-use crate::constants::{ATTACKS, COLOR_MASK, KNIGHT_DELTAS, QUEEN_DELTAS};
-use crate::error::Error;
-use crate::move_gen::MoveGen;
+use crate::clock::GameClock;
+use crate::constants::{ATTACKS, COLOR_MASK, DEFAULT_FEN, KNIGHT_DELTAS, QUEEN_DELTAS};
+use crate::dead_position;
+use crate::error::{Error, FenField, Violation};
+use crate::move_gen::{GenMode, MoveGen};
+use crate::move_list::{LegalMoves, MoveList};
+use crate::outcome::{Outcome, Termination};
+use crate::perft::{self, PerftConfig};
+use crate::pgn;
 use crate::piece::{PType, Piece};
+use crate::san;
 use crate::square::Square;
 use crate::utils;
+use crate::zobrist;
 use serde::{Deserialize, Serialize};
 use std::cmp;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
+use std::sync::OnceLock;
 
-#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum Color {
     WHITE = 0,
     BLACK = 128,
 }
 
+impl Color {
+    pub fn opponent(self) -> Self {
+        match self {
+            Color::WHITE => Color::BLACK,
+            Color::BLACK => Color::WHITE,
+        }
+    }
+}
+
 impl TryFrom<&str> for Color {
     type Error = Error;
     fn try_from(value: &str) -> Result<Self, Self::Error> {
@@ -43,7 +61,6 @@ pub struct Move {
     pub promotion_piece: Option<Piece>,
 }
 
-// TODO: implement algebraic notation for moves
 impl Move {
     pub fn new(from: Square, to: Square, promotion_piece: Option<Piece>) -> Self {
         Self {
@@ -60,9 +77,54 @@ impl Move {
             promotion_piece,
         }
     }
+
+    // long algebraic notation as used by UCI, e.g. e2e4, e7e8q
+    pub fn to_uci(&self) -> String {
+        let mut uci = format!("{}{}", self.from.get_notation(), self.to.get_notation());
+
+        if let Some(ref promotion_piece) = self.promotion_piece {
+            let p: String = Piece::new(promotion_piece.p_type, Color::BLACK).into();
+            uci.push_str(&p);
+        }
+
+        uci
+    }
+
+    pub fn from_uci(uci: &str) -> Result<Self, Error> {
+        if !uci.is_ascii() || (uci.len() != 4 && uci.len() != 5) {
+            return Err(Error::InvalidUci);
+        }
+
+        let from: Square = uci[0..2].try_into().map_err(|_| Error::InvalidUci)?;
+        let to: Square = uci[2..4].try_into().map_err(|_| Error::InvalidUci)?;
+
+        let promotion_piece = match uci.get(4..5) {
+            Some(p) => {
+                // the promotion letter is always lowercase in UCI, so the color comes from the rank
+                let color = match to.rank() {
+                    7 => Color::WHITE,
+                    0 => Color::BLACK,
+                    _ => return Err(Error::InvalidUci),
+                };
+
+                let p_type = match p {
+                    "q" => PType::QUEEN,
+                    "r" => PType::ROOK,
+                    "b" => PType::BISHOP,
+                    "n" => PType::KNIGHT,
+                    _ => return Err(Error::InvalidUci),
+                };
+
+                Some(Piece::new(p_type, color))
+            }
+            None => None,
+        };
+
+        Ok(Self::new(from, to, promotion_piece))
+    }
 }
 
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Debug)]
 struct CastlingRights {
     white_kingside: bool,
     white_queenside: bool,
@@ -70,28 +132,74 @@ struct CastlingRights {
     black_queenside: bool,
 }
 
-struct HistoryEntry {
-    m: Move,
-    piece: Piece,
+impl CastlingRights {
+    fn hash(&self) -> u64 {
+        let rights = [
+            self.white_kingside,
+            self.white_queenside,
+            self.black_kingside,
+            self.black_queenside,
+        ];
+
+        rights
+            .iter()
+            .enumerate()
+            .filter(|(_, has_right)| **has_right)
+            .fold(0, |hash, (i, _)| hash ^ zobrist::castling(i))
+    }
+}
+
+// everything make_unchecked overwrites that can't be worked out again from the move
+#[derive(Clone, Debug)]
+pub struct Undo {
+    captured: Option<Capture>,
     castling_rights: CastlingRights,
     en_passant_sq: Option<Square>,
-    half_moves: u8,
-    full_moves: u8,
-    is_queenside_castle: bool,
-    is_kingside_castle: bool,
-    has_moved: bool,
-    unique_positions: HashMap<String, u8>,
-    side_to_move: Color,
-    is_capture: bool,
-    check_rays: HashSet<Square>,
+    half_moves: u16,
+    full_moves: u16,
+    is_in_check: bool,
+    hash: u64,
+}
+
+#[derive(Default)]
+struct Material {
+    knights: u8,
+    light_square_bishops: u8,
+    dark_square_bishops: u8,
+    // pawns, rooks and queens, any one of which is enough to mate
+    majors_and_pawns: u8,
+}
+
+#[derive(Clone)]
+struct HistoryEntry {
+    m: Move,
+    undo: Undo,
+    // the repetition key of the position the move was played from
+    key: u64,
+}
+
+// the square delta of a single pawn push
+fn pawn_push(color: Color) -> i8 {
+    match color {
+        Color::WHITE => 16,
+        Color::BLACK => -16,
+    }
+}
+
+fn fen_error(field: FenField, reason: &str) -> Error {
+    Error::InvalidFen {
+        field,
+        reason: reason.to_string(),
+    }
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Capture {
     sq: Square,
     piece: Piece,
 }
 
+#[derive(Clone)]
 pub struct GameState {
     pub board: Board,
     pub en_passant_sq: Option<Square>,
@@ -99,22 +207,16 @@ pub struct GameState {
     pub is_in_check: bool,
     pub white_king_square: Option<Square>,
     pub black_king_square: Option<Square>,
-    check_rays: HashSet<Square>,
     castling_rights: CastlingRights,
 
     pub captures: Vec<Capture>,
-    unique_positions: HashMap<String, u8>,
+    hash: u64,
 
-    half_moves: u8,
-    full_moves: u8,
+    half_moves: u16,
+    full_moves: u16,
     debug: bool,
 
     history: Vec<HistoryEntry>,
-
-    has_moved: bool,
-    captures_count: usize,
-    castle_count: usize,
-    check_count: usize,
 }
 
 impl GameState {
@@ -124,7 +226,6 @@ impl GameState {
             board: Board::new(),
             en_passant_sq: None,
             is_in_check: false,
-            check_rays: HashSet::new(),
             white_king_square: None,
             black_king_square: None,
             castling_rights: CastlingRights::default(),
@@ -132,11 +233,7 @@ impl GameState {
             debug: false,
             full_moves: 0,
             half_moves: 0,
-            unique_positions: HashMap::new(),
-            has_moved: false,
-            captures_count: 0,
-            castle_count: 0,
-            check_count: 0,
+            hash: 0,
 
             history: vec![],
         }
@@ -158,185 +255,190 @@ impl GameState {
         }
 
         // TODO remove this later
-        let legal_moves = MoveGen::moves_for_square(self, m.from);
-        if !legal_moves.contains(&m) || legal_moves.len() == 0 {
+        let mut legal_moves = MoveList::new();
+        MoveGen::legal_moves_for_square(self, m.from, &mut legal_moves);
+        if !legal_moves.contains(&m) || legal_moves.is_empty() {
             return Err(Error::IllegalMove);
         }
 
         self.make_move(m);
-        self.change_turn();
-        self.update_king_attacks();
 
         Ok(())
     }
 
     // this method assumes all moves are valid, and there must be a piece being moved
     pub fn make_move(&mut self, m: Move) {
+        let key = self.repetition_key();
+        let undo = self.make_unchecked(&m);
+
+        if let Some(ref capture) = undo.captured {
+            self.captures.push(capture.clone());
+        }
+        self.history.push(HistoryEntry { m, undo, key });
+    }
+
+    // takes back the last move in history and returns it
+    pub fn undo(&mut self) -> Option<Move> {
+        let entry = self.history.pop()?;
+
+        if entry.undo.captured.is_some() {
+            self.captures.pop().expect("capture MUST be in history");
+        }
+
+        self.unmake(&entry.m, entry.undo);
+
+        Some(entry.m)
+    }
+
+    // plays a whole ply without validating it or recording any history, the move must be legal.
+    // the returned Undo takes the position back with unmake
+    pub fn make_unchecked(&mut self, m: &Move) -> Undo {
         let piece = self
             .board
             .get(&m.from)
-            .expect("a piece must be present in make_move")
+            .expect("a piece must be present in make_unchecked")
             .clone();
 
-        let mut is_capture = false;
-        if self.is_capture(&m) {
-            if self.is_enpassant_capture(&m) {
-                let delta: i8 = match piece.color {
-                    Color::WHITE => -16,
-                    Color::BLACK => 16,
-                };
-                let to =
-                    m.to.add(delta)
-                        .expect("en passant square must be valid here");
-
-                self.board.remove(&to);
-
-                self.captures.push(Capture {
-                    sq: to,
-                    piece: Piece::new(
-                        PType::PAWN,
-                        if piece.color == Color::WHITE {
-                            Color::BLACK
-                        } else {
-                            Color::WHITE
-                        },
-                    ),
-                });
-            } else {
-                self.captures.push(Capture {
-                    sq: m.to.clone(),
-                    piece: self
-                        .board
-                        .get(&m.to)
-                        .expect("capture must have a captured piece")
-                        .clone(),
-                });
-            }
-            is_capture = true;
-        }
-
-        // TODO: refactor this
-        let mut is_queenside_castle = false;
-        let mut is_kingside_castle = false;
-        if self.board.get(&m.from).unwrap().p_type == PType::KING {
-            if self.is_castling_kingside(&m) {
-                is_kingside_castle = true;
-                // remove the rook
-                self.board
-                    .remove(&m.to.add(1).expect("castling square must be valid"));
-                // put a new rook to the left
+        let captured_sq = if self.is_enpassant_capture(m) {
+            m.to.add(pawn_push(piece.color.opponent()))
+                .expect("en passant square must be valid here")
+        } else {
+            m.to
+        };
+        let captured = match self.board.get(&captured_sq) {
+            Some(p) if p.color != piece.color => Some(Capture {
+                sq: captured_sq,
+                piece: p.clone(),
+            }),
+            _ => None,
+        };
+
+        let undo = Undo {
+            captured,
+            castling_rights: self.castling_rights.clone(),
+            en_passant_sq: self.en_passant_sq,
+            half_moves: self.half_moves,
+            full_moves: self.full_moves,
+            is_in_check: self.is_in_check,
+            hash: self.hash,
+        };
+
+        if let Some(ref capture) = undo.captured {
+            self.remove(&capture.sq);
+        }
+
+        if piece.p_type == PType::KING {
+            if self.is_castling_kingside(m) {
+                self.remove(&m.to.add(1).expect("castling square must be valid"));
                 self.set(
                     Piece::new(PType::ROOK, piece.color),
                     &m.to.add(-1).expect("castling square must be valid"),
                 );
-                self.castle_count += 1;
-            } else if self.is_castling_queenside(&m) {
-                is_queenside_castle = true;
-                // remove the rook
-                self.board
-                    .remove(&m.to.add(-2).expect("castling square must be valid"));
-                // put a new rook to the right
+            } else if self.is_castling_queenside(m) {
+                self.remove(&m.to.add(-2).expect("castling square must be valid"));
                 self.set(
                     Piece::new(PType::ROOK, piece.color),
                     &m.to.add(1).expect("castling square must be valid"),
                 );
-                self.castle_count += 1;
             }
         }
 
-        // TODO: we can do better
-        self.history.push(HistoryEntry {
-            m: m.clone(),
-            piece: piece.clone(),
-            castling_rights: self.castling_rights.clone(),
-            full_moves: self.full_moves,
-            half_moves: self.half_moves,
-            en_passant_sq: self.en_passant_sq,
-            is_kingside_castle,
-            is_queenside_castle,
-            has_moved: self.has_moved,
-            unique_positions: self.unique_positions.clone(),
-            side_to_move: self.side_to_move,
-            is_capture,
-            check_rays: self.check_rays.clone(),
-        });
-
-        if self.is_enpassant_move(&m) {
-            let delta: i8 = match piece.color {
-                Color::WHITE => -16,
-                Color::BLACK => 16,
-            };
-
-            self.en_passant_sq = Some(m.to.add(delta).expect("en passant square must be valid"));
+        if let Some(sq) = self.en_passant_sq {
+            self.hash ^= zobrist::en_passant(sq);
+        }
+        if self.is_enpassant_move(m) {
+            let sq =
+                m.to.add(pawn_push(piece.color.opponent()))
+                    .expect("en passant square must be valid");
+            self.en_passant_sq = Some(sq);
+            self.hash ^= zobrist::en_passant(sq);
         } else {
             self.en_passant_sq = None;
         }
 
-        self.has_moved = true;
-        self.full_moves += 1;
+        if piece.color == Color::BLACK {
+            self.full_moves += 1;
+        }
 
-        if piece.p_type == PType::PAWN || self.is_capture(&m) {
+        if piece.p_type == PType::PAWN || undo.captured.is_some() {
             self.half_moves = 0;
         } else {
             self.half_moves += 1;
         }
 
-        if let Some(ref promo_piece) = m.promotion_piece {
-            self.set(promo_piece.clone(), &m.to);
-        } else {
-            self.set(piece.clone(), &m.to);
+        self.remove(&m.from);
+        match m.promotion_piece {
+            Some(ref promo_piece) => self.set(promo_piece.clone(), &m.to),
+            None => self.set(piece.clone(), &m.to),
         }
 
-        self.board.remove(&m.from);
-        self.update_castling_rights(&piece, &m);
-        self.update_positions();
+        self.hash ^= self.castling_rights.hash();
+        self.update_castling_rights(&piece, m);
+        self.hash ^= self.castling_rights.hash();
+
+        self.change_turn();
+        self.update_king_attacks();
+
+        undo
     }
 
-    pub fn undo(&mut self) {
-        if let Some(entry) = self.history.pop() {
-            let piece = entry.piece;
+    // takes back a move played with make_unchecked, using the Undo it returned
+    pub fn unmake(&mut self, m: &Move, undo: Undo) {
+        self.change_turn();
+
+        let piece = match m.promotion_piece {
+            Some(_) => Piece::new(PType::PAWN, self.side_to_move),
+            None => self
+                .board
+                .get(&m.to)
+                .expect("the moved piece must be present in unmake")
+                .clone(),
+        };
 
-            self.set(piece.clone(), &entry.m.from);
-            self.board.remove(&entry.m.to);
+        self.remove(&m.to);
+        self.set(piece.clone(), &m.from);
 
-            if entry.is_kingside_castle {
-                // remove the rook
-                self.board.remove(&entry.m.from.add(1).unwrap());
+        if piece.p_type == PType::KING {
+            if self.is_castling_kingside(m) {
+                self.remove(&m.to.add(-1).expect("castling square must be valid"));
                 self.set(
                     Piece::new(PType::ROOK, piece.color),
-                    &entry.m.from.add(3).unwrap(),
+                    &m.to.add(1).expect("castling square must be valid"),
                 );
-            } else if entry.is_queenside_castle {
-                self.board.remove(&entry.m.from.add(-1).unwrap());
+            } else if self.is_castling_queenside(m) {
+                self.remove(&m.to.add(1).expect("castling square must be valid"));
                 self.set(
                     Piece::new(PType::ROOK, piece.color),
-                    &entry.m.from.add(-4).unwrap(),
+                    &m.to.add(-2).expect("castling square must be valid"),
                 );
             }
+        }
 
-            self.full_moves = entry.full_moves;
-            self.half_moves = entry.half_moves;
-            self.has_moved = entry.has_moved;
-            self.unique_positions = entry.unique_positions;
-            self.side_to_move = entry.side_to_move;
-            self.castling_rights = entry.castling_rights;
-            self.en_passant_sq = entry.en_passant_sq;
-            self.check_rays = entry.check_rays;
-            self.is_in_check = self.check_rays.len() > 0;
-
-            if entry.is_capture {
-                let c = self.captures.pop().expect("capture MUST be in history");
-                self.board.set(c.piece, &c.sq);
-            }
-        };
+        if let Some(capture) = undo.captured {
+            self.set(capture.piece, &capture.sq);
+        }
+
+        self.castling_rights = undo.castling_rights;
+        self.en_passant_sq = undo.en_passant_sq;
+        self.half_moves = undo.half_moves;
+        self.full_moves = undo.full_moves;
+        self.is_in_check = undo.is_in_check;
+        self.hash = undo.hash;
     }
 
+    // set-up positions may not have a king, in which case there is nothing to be checked
     fn update_king_attacks(&mut self) {
-        let king_sq: Square = self.get_current_king_sq().expect("no king to check");
+        self.is_in_check = self
+            .get_current_king_sq()
+            .is_some_and(|king_sq| self.is_attacked(king_sq));
+    }
 
-        // *self.check_count.borrow_mut() = 0;
-        self.check_rays = self.get_attack_rays(king_sq);
-        self.is_in_check = self.check_rays.len() > 0;
+    // the squares between the king and its checkers, including the checkers themselves
+    pub fn check_rays(&self) -> HashSet<Square> {
+        match self.get_current_king_sq() {
+            Some(king_sq) => self.get_attack_rays(king_sq),
+            None => HashSet::new(),
+        }
     }
 
     fn get_attack_rays(&self, sq: Square) -> HashSet<Square> {
@@ -405,32 +507,32 @@ impl GameState {
             }
         }
 
-        return attack_rays;
+        attack_rays
     }
 
     pub fn is_attacked(&self, sq: Square) -> bool {
-        return self.get_attack_rays(sq).len() > 0;
+        MoveGen::is_attacked(&self.board, sq, self.side_to_move.opponent())
     }
 
     pub fn get_current_king_sq(&self) -> Option<Square> {
         if self.side_to_move == Color::WHITE {
-            return self.white_king_square;
+            self.white_king_square
         } else {
-            return self.black_king_square;
+            self.black_king_square
         }
     }
 
     pub fn get_castling_rights(&self) -> (bool, bool) {
         if self.side_to_move == Color::WHITE {
-            return (
+            (
                 self.castling_rights.white_kingside,
                 self.castling_rights.white_queenside,
-            );
+            )
         } else {
-            return (
+            (
                 self.castling_rights.black_kingside,
                 self.castling_rights.black_queenside,
-            );
+            )
         }
     }
 
@@ -502,22 +604,13 @@ impl GameState {
         }
     }
 
-    fn update_positions(&mut self) {
-        let fen = self.get_fen();
-        let fen_parts: Vec<&str> = fen.split(" ").collect();
-
-        *self
-            .unique_positions
-            .entry(fen_parts[0].to_string())
-            .or_insert(0) += 1;
-    }
-
     fn change_turn(&mut self) {
         if self.side_to_move == Color::WHITE {
             self.side_to_move = Color::BLACK;
         } else {
             self.side_to_move = Color::WHITE;
         }
+        self.hash ^= zobrist::side();
     }
 
     pub fn reset(&mut self) {
@@ -525,7 +618,6 @@ impl GameState {
         self.board = Board::new();
         self.en_passant_sq = None;
         self.is_in_check = false;
-        self.check_rays = HashSet::new();
         self.white_king_square = None;
         self.black_king_square = None;
         self.castling_rights = CastlingRights::default();
@@ -533,34 +625,57 @@ impl GameState {
         self.debug = false;
         self.full_moves = 0;
         self.half_moves = 0;
-        self.unique_positions = HashMap::new();
-        self.has_moved = false;
+        self.hash = 0;
+        self.history = vec![];
+    }
+
+    // moves are generated once, and only until the first legal one turns up
+    fn outcome(&self) -> Option<Outcome> {
+        if LegalMoves::new(self).next().is_none() {
+            return Some(if self.is_in_check {
+                Outcome::win(self.side_to_move.opponent(), Termination::Checkmate)
+            } else {
+                Outcome::draw(Termination::Stalemate)
+            });
+        }
+
+        if self.is_insufficient_material() {
+            return Some(Outcome::draw(Termination::InsufficientMaterial));
+        }
+
+        if dead_position::is_locked(self) {
+            return Some(Outcome::draw(Termination::DeadPosition));
+        }
+
+        if self.is_75_moves() {
+            return Some(Outcome::draw(Termination::SeventyFiveMoves));
+        }
+
+        if self.is_fivefold_repetition() {
+            return Some(Outcome::draw(Termination::FivefoldRepetition));
+        }
+
+        None
     }
 
-    fn is_draw(&mut self) -> bool {
-        self.is_stalemate()
-            || self.is_threefold_repetition()
-            || self.is_50_moves()
-            || self.is_insufficient_material()
+    // a draw the side to move may claim, which doesn't end the game until it is claimed
+    fn claimable_draw(&self) -> Option<Termination> {
+        if self.is_threefold_repetition() {
+            Some(Termination::ThreefoldRepetition)
+        } else if self.is_50_moves() {
+            Some(Termination::FiftyMoves)
+        } else {
+            None
+        }
     }
 
-    fn is_stalemate(&mut self) -> bool {
-        !self.is_in_check && MoveGen::moves(self, self.side_to_move).len() == 0
+    fn is_stalemate(&self) -> bool {
+        !self.is_in_check && MoveGen::moves(self, self.side_to_move).is_empty()
     }
 
-    // https://www.chess.com/article/view/how-chess-games-can-end-8-ways-explained#insufficient-material
-    // TODO: i think we can do better
-    fn is_insufficient_material(&self) -> bool {
-        let mut w_knights = 0;
-        let mut b_knights = 0;
-        let mut w_bishops = 0;
-        let mut b_bishops = 0;
-        let mut w_light_square_bishops = 0;
-        let mut w_dark_square_bishops = 0;
-        let mut b_light_square_bishops = 0;
-        let mut b_dark_square_bishops = 0;
-        let mut knights = 0;
-        let mut bishops = 0;
+    // the pieces of one color, kings left out
+    fn material(&self, color: Color) -> Material {
+        let mut material = Material::default();
 
         for idx in 0..SIZE_0X88 {
             let idx = idx as u8;
@@ -573,96 +688,144 @@ impl GameState {
                 continue;
             };
 
-            if piece.p_type == PType::PAWN
-                || piece.p_type == PType::ROOK
-                || piece.p_type == PType::QUEEN
-            {
-                return false;
+            if piece.color != color {
+                continue;
             }
 
-            let square: Square = idx.try_into().unwrap();
-
-            if piece.color == Color::WHITE {
-                if piece.p_type == PType::KNIGHT {
-                    w_knights += 1;
-                    knights += 1;
-                }
-
-                if piece.p_type == PType::BISHOP {
-                    if square.color() == Color::WHITE {
-                        w_light_square_bishops += 1;
-                    } else {
-                        w_dark_square_bishops += 1;
-                    }
-
-                    w_bishops += 1;
-                    bishops += 1;
-                }
-            } else {
-                if piece.p_type == PType::KNIGHT {
-                    b_knights += 1;
-                    knights += 1;
-                }
-
-                if piece.p_type == PType::BISHOP {
-                    if square.color() == Color::WHITE {
-                        b_light_square_bishops += 1;
-                    } else {
-                        b_dark_square_bishops += 1;
-                    }
-
-                    b_bishops += 1;
-                    bishops += 1;
-                }
+            match piece.p_type {
+                PType::KING => {}
+                PType::KNIGHT => material.knights += 1,
+                PType::BISHOP => match Square(idx).color() {
+                    Color::WHITE => material.light_square_bishops += 1,
+                    Color::BLACK => material.dark_square_bishops += 1,
+                },
+                _ => material.majors_and_pawns += 1,
             }
         }
 
-        // king vs king
-        if knights == 0 && bishops == 0 {
-            return true;
-        }
+        material
+    }
 
-        // king + minor piece vs king
-        if (knights == 1 && bishops == 0) || (bishops == 1 && knights == 0) {
-            return true;
+    // https://www.chess.com/article/view/how-chess-games-can-end-8-ways-explained#insufficient-material
+    // nothing but kings and either a single minor piece or bishops that all stand on squares of
+    // one color. two knights, or a knight against a bishop, can still mate with some help
+    fn is_insufficient_material(&self) -> bool {
+        let white = self.material(Color::WHITE);
+        let black = self.material(Color::BLACK);
+
+        if white.majors_and_pawns + black.majors_and_pawns > 0 {
+            return false;
         }
 
-        // king + two knights vs king
-        // if (w_knights == 2 && bishops == 0) || (b_knights == 2 && bishops == 0) {
-        //     return true;
-        // }
+        let knights = white.knights + black.knights;
+        let light_square_bishops = white.light_square_bishops + black.light_square_bishops;
+        let dark_square_bishops = white.dark_square_bishops + black.dark_square_bishops;
 
-        // king + minor piece vs king + minor piece (minor piece is different kind)
-        // if (w_knights == 1 && b_bishops == 1) || (w_bishops == 1 && b_knights == 1) {
-        //     return true;
-        // }
+        knights + light_square_bishops + dark_square_bishops <= 1
+            || (knights == 0 && (light_square_bishops == 0 || dark_square_bishops == 0))
+    }
 
-        // king + bishop vs king + bishop (same color bishops)
-        if (w_dark_square_bishops == 1 && b_dark_square_bishops == 1)
-            || (w_light_square_bishops == 1 && b_light_square_bishops == 1)
-        {
-            return true;
-        }
+    // whether some sequence of legal moves, however unlikely, ends with color mating. a lone
+    // knight needs an enemy piece to hem the king in, and bishops of one square color need one
+    // that can stand on the other color
+    fn can_checkmate(&self, color: Color) -> bool {
+        let own = self.material(color);
+        let other = self.material(color.opponent());
 
-        if w_dark_square_bishops != 0 && b_dark_square_bishops != 0 && knights == 0 {
+        if own.majors_and_pawns > 0 {
             return true;
         }
 
-        if w_light_square_bishops != 0 && b_light_square_bishops != 0 && knights == 0 {
-            return true;
+        let bishops = own.light_square_bishops + own.dark_square_bishops;
+        let other_pieces = other.majors_and_pawns
+            + other.knights
+            + other.light_square_bishops
+            + other.dark_square_bishops;
+
+        match (own.knights, bishops) {
+            (0, 0) => false,
+            (1, 0) => other_pieces > 0,
+            (0, _) if own.light_square_bishops == 0 => {
+                other.majors_and_pawns + other.knights + other.light_square_bishops > 0
+            }
+            (0, _) if own.dark_square_bishops == 0 => {
+                other.majors_and_pawns + other.knights + other.dark_square_bishops > 0
+            }
+            _ => true,
         }
+    }
 
-        false
+    // https://www.fide.com/FIDE/handbook/LawsOfChess.pdf, article 5.2.2
+    // no sequence of legal moves can end in mate, so the game is drawn right away
+    fn is_dead_position(&self) -> bool {
+        self.is_insufficient_material() || dead_position::is_locked(self)
     }
 
+    // fifty moves by each side without a capture or a pawn move, a draw may be claimed
     fn is_50_moves(&self) -> bool {
         self.half_moves >= 100
     }
 
-    // TODO: this may not work as intended
+    // seventy-five moves by each side, the game is drawn unless the last move mated
+    fn is_75_moves(&self) -> bool {
+        self.half_moves >= 150
+    }
+
+    // a player may claim a draw once the current position has appeared three times
     fn is_threefold_repetition(&self) -> bool {
-        for (_, position_count) in &self.unique_positions {
-            if *position_count >= 3 {
+        self.repetition_count() >= 3
+    }
+
+    // the game is drawn automatically once the current position has appeared five times
+    fn is_fivefold_repetition(&self) -> bool {
+        self.repetition_count() >= 5
+    }
+
+    // how many times the current position has appeared, counting this one.
+    // captures and pawn moves can't be undone, so only the positions since the
+    // last one (the half move clock) need to be looked at
+    fn repetition_count(&self) -> usize {
+        let key = self.repetition_key();
+        let window = cmp::min(self.half_moves as usize, self.history.len());
+
+        // the same player is to move every other ply
+        let repeats = (2..=window)
+            .step_by(2)
+            .filter(|plies| self.history[self.history.len() - plies].key == key)
+            .count();
+
+        repeats + 1
+    }
+
+    // under FIDE rules positions are the same when the same player is to move with the
+    // same pieces, castling rights and en passant captures. an en passant square nobody
+    // can legally capture on doesn't make a position different
+    fn repetition_key(&self) -> u64 {
+        let en_passant_sq = self.en_passant_sq;
+        match en_passant_sq {
+            Some(sq) if !self.has_legal_en_passant() => self.hash ^ zobrist::en_passant(sq),
+            _ => self.hash,
+        }
+    }
+
+    fn has_legal_en_passant(&self) -> bool {
+        let Some(ep_sq) = self.en_passant_sq else {
+            return false;
+        };
+        let pawn = Piece::new(PType::PAWN, self.side_to_move);
+        let deltas: [i8; 2] = match self.side_to_move {
+            Color::WHITE => [-15, -17],
+            Color::BLACK => [15, 17],
+        };
+
+        for delta in deltas {
+            let Ok(from) = ep_sq.add(delta) else {
+                continue;
+            };
+
+            if self.board.get(&from) == Some(&pawn)
+                && MoveGen::moves_for_square(self, from).contains(&Move::new(from, ep_sq, None))
+            {
                 return true;
             }
         }
@@ -670,19 +833,15 @@ impl GameState {
         false
     }
 
-    fn is_checkmate(&mut self) -> bool {
-        self.is_in_check && MoveGen::moves(self, self.side_to_move).len() == 0
+    pub(crate) fn is_checkmate(&self) -> bool {
+        self.is_in_check && MoveGen::moves(self, self.side_to_move).is_empty()
     }
 
     pub fn is_occupied(&self, sq: &Square) -> bool {
-        return self.board.get(sq).is_some();
+        self.board.get(sq).is_some()
     }
 
-    fn is_friendly(&self, piece: &Piece) -> bool {
-        return piece.color == self.side_to_move;
-    }
-
-    fn is_enpassant_capture(&self, m: &Move) -> bool {
+    pub(crate) fn is_enpassant_capture(&self, m: &Move) -> bool {
         self.board.get(&m.from).unwrap().p_type == PType::PAWN && Some(m.to) == self.en_passant_sq
     }
 
@@ -707,10 +866,10 @@ impl GameState {
             }
         }
 
-        return false;
+        false
     }
 
-    fn is_capture(&self, m: &Move) -> bool {
+    pub(crate) fn is_capture(&self, m: &Move) -> bool {
         let Some(p) = self.board.get(&m.to) else {
             let a = self.is_enpassant_capture(m);
             return a;
@@ -745,129 +904,373 @@ impl GameState {
 
     fn set(&mut self, piece: Piece, sq: &Square) {
         if piece.p_type == PType::KING {
-            self.update_king_sq(sq.clone(), piece.color);
+            self.update_king_sq(*sq, piece.color);
         }
+        self.remove(sq);
+        self.hash ^= zobrist::piece(&piece, *sq);
         self.board.set(piece, sq);
     }
 
-    // TODO: handle error while parsing FEN, full moves
-    pub fn load_fen(&mut self, fen: &str) {
-        let fen_parts: Vec<&str> = fen.split(" ").collect();
+    fn remove(&mut self, sq: &Square) {
+        if let Some(piece) = self.board.get(sq) {
+            self.hash ^= zobrist::piece(piece, *sq);
+            self.board.remove(sq);
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
 
-        let mut ranks: Vec<&str> = fen_parts[0].split("/").collect();
-        ranks.reverse();
+    // the incrementally updated hash must always match this
+    fn compute_hash(&self) -> u64 {
+        let mut hash = self.castling_rights.hash();
 
-        for rank_idx in 0..ranks.len() {
-            let mut file_idx: u8 = 0;
+        for idx in 0..SIZE_0X88 {
+            let idx = idx as u8;
+
+            if utils::is_valid_idx(idx).is_err() {
+                continue;
+            }
+
+            if let Some(piece) = self.board.get(&idx) {
+                hash ^= zobrist::piece(piece, Square(idx));
+            }
+        }
+
+        if let Some(sq) = self.en_passant_sq {
+            hash ^= zobrist::en_passant(sq);
+        }
+        if self.side_to_move == Color::BLACK {
+            hash ^= zobrist::side();
+        }
+
+        hash
+    }
+
+    // the position is parsed into a fresh state first, so a bad FEN never leaves this one half-loaded
+    pub fn load_fen(&mut self, fen: &str) -> Result<(), Error> {
+        use FenField::*;
+
+        let mut state = GameState::new();
+        let mut fen_parts = fen.split_whitespace();
 
-            for c in ranks[rank_idx].chars() {
-                let sq = Square::new((rank_idx as u8).into(), file_idx.into());
+        let mut next_part = |field: FenField| {
+            fen_parts
+                .next()
+                .ok_or_else(|| fen_error(field, "missing field"))
+        };
+
+        let placement = next_part(Placement)?;
+        let turn = next_part(Turn)?;
+        let castling_rights = next_part(Castling)?;
+        let en_passant_square = next_part(EnPassant)?;
+        let half_moves = next_part(HalfMoves)?;
+        let full_moves = next_part(FullMoves)?;
+
+        if fen_parts.next().is_some() {
+            return Err(fen_error(
+                FullMoves,
+                "unexpected data after the full move number",
+            ));
+        }
+
+        // piece placement, from the 8th rank down to the 1st
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(fen_error(
+                Placement,
+                &format!("expected 8 ranks, found {}", ranks.len()),
+            ));
+        }
+
+        for (rank_idx, rank) in ranks.iter().rev().enumerate() {
+            let mut file_idx: u8 = 0;
+            let mut last_was_digit = false;
+
+            for c in rank.chars() {
+                if file_idx >= 8 {
+                    return Err(fen_error(
+                        Placement,
+                        &format!("rank {} has more than 8 files", rank_idx + 1),
+                    ));
+                }
 
                 match c {
                     '1'..='8' => {
-                        file_idx += (c.to_digit(10).unwrap() as usize - 1) as u8;
+                        if last_was_digit {
+                            return Err(fen_error(
+                                Placement,
+                                &format!(
+                                    "rank {} has consecutive empty square counts",
+                                    rank_idx + 1
+                                ),
+                            ));
+                        }
+
+                        file_idx += c.to_digit(10).unwrap() as u8;
                     }
                     _ => {
-                        self.set(
-                            c.to_string()
-                                .as_str()
-                                .try_into()
-                                .expect("invalid FEN characters"),
-                            &sq,
-                        );
+                        let piece: Piece = c.to_string().as_str().try_into().map_err(|_| {
+                            fen_error(Placement, &format!("invalid piece character '{}'", c))
+                        })?;
+                        let sq = Square::new((rank_idx as u8).into(), file_idx.into());
+
+                        state.set(piece, &sq);
+                        file_idx += 1;
                     }
                 };
 
-                file_idx += 1;
+                last_was_digit = c.is_ascii_digit();
             }
-        }
 
-        //set turn
-        match fen_parts[1] {
-            "w" => self.side_to_move = Color::WHITE,
-            "b" => self.side_to_move = Color::BLACK,
-            _ => panic!("invalid FEN turn"),
+            if file_idx != 8 {
+                return Err(fen_error(
+                    Placement,
+                    &format!("rank {} does not add up to 8 files", rank_idx + 1),
+                ));
+            }
         }
 
-        //castling rights
-        for castling_right in fen_parts[2].chars() {
-            match castling_right {
-                'K' => {
-                    self.castling_rights.white_kingside = true;
-                }
-                'Q' => {
-                    self.castling_rights.white_queenside = true;
-                }
-                'k' => {
-                    self.castling_rights.black_kingside = true;
-                }
-                'q' => {
-                    self.castling_rights.black_queenside = true;
-                }
+        state.side_to_move = turn
+            .try_into()
+            .map_err(|_| fen_error(Turn, &format!("expected 'w' or 'b', found '{}'", turn)))?;
+
+        if castling_rights != "-" {
+            for castling_right in castling_rights.chars() {
+                let right = match castling_right {
+                    'K' => &mut state.castling_rights.white_kingside,
+                    'Q' => &mut state.castling_rights.white_queenside,
+                    'k' => &mut state.castling_rights.black_kingside,
+                    'q' => &mut state.castling_rights.black_queenside,
+                    _ => {
+                        return Err(fen_error(
+                            Castling,
+                            &format!("invalid castling right '{}'", castling_right),
+                        ))
+                    }
+                };
 
-                '-' => {
-                    self.castling_rights.white_kingside = false;
-                    self.castling_rights.white_queenside = false;
-                    self.castling_rights.black_kingside = false;
-                    self.castling_rights.black_kingside = false;
+                if *right {
+                    return Err(fen_error(
+                        Castling,
+                        &format!("duplicate castling right '{}'", castling_right),
+                    ));
                 }
-                _ => panic!("cant load fen castling rights"),
+
+                *right = true;
             }
         }
 
-        // en passant square
-        let en_passant_square = fen_parts[3];
+        if en_passant_square != "-" {
+            let sq: Square = en_passant_square.try_into().map_err(|_| {
+                fen_error(
+                    EnPassant,
+                    &format!("invalid square '{}'", en_passant_square),
+                )
+            })?;
+
+            // the en passant square is behind the pawn that just moved two squares
+            let expected_rank = match state.side_to_move {
+                Color::WHITE => 5,
+                Color::BLACK => 2,
+            };
 
-        match en_passant_square {
-            "-" => {
-                self.en_passant_sq = None;
-            }
-            _ => {
-                self.en_passant_sq = Some(
-                    en_passant_square
-                        .try_into()
-                        .expect("invalid en passant square in FEN"),
-                );
+            if sq.rank() != expected_rank {
+                return Err(fen_error(
+                    EnPassant,
+                    &format!(
+                        "{} can't be an en passant square with {} to move",
+                        en_passant_square, turn
+                    ),
+                ));
             }
+
+            state.en_passant_sq = Some(sq);
+        }
+
+        state.half_moves = half_moves.parse().map_err(|_| {
+            fen_error(
+                HalfMoves,
+                &format!("invalid half move clock '{}'", half_moves),
+            )
+        })?;
+
+        state.full_moves = full_moves.parse().map_err(|_| {
+            fen_error(
+                FullMoves,
+                &format!("invalid full move number '{}'", full_moves),
+            )
+        })?;
+
+        if state.full_moves == 0 {
+            return Err(fen_error(FullMoves, "the full move number starts at 1"));
         }
 
-        self.half_moves = fen_parts[4].parse().expect("can't parse FEN half moves");
-        self.full_moves = fen_parts[5].parse().expect("can't parse FEN full moves");
+        state.hash = state.compute_hash();
+        state.update_king_attacks();
 
-        *self
-            .unique_positions
-            .entry(fen_parts[0].to_string())
-            .or_insert(0) += 1;
+        *self = state;
 
-        self.update_king_attacks();
+        Ok(())
     }
 
-    // TODO refactor this
-    pub fn get_fen(&self) -> String {
-        let mut empty_count: u8 = 0;
+    // checks that a loaded position could have been reached in a real game
+    pub fn validate(&self) -> Vec<Violation> {
+        let mut violations = vec![];
 
-        let mut rank = String::new();
-        let mut ranks: Vec<String> = vec![];
+        for color in [Color::WHITE, Color::BLACK] {
+            let kings = (0..SIZE_0X88 as u8)
+                .filter(|idx| utils::is_valid_idx(*idx).is_ok())
+                .filter(|idx| self.board.get(idx) == Some(&Piece::new(PType::KING, color)))
+                .count();
 
-        for idx in 0..128 {
-            if utils::is_valid_idx(idx).is_err() {
-                continue;
+            match kings {
+                0 => violations.push(Violation::MissingKing(color)),
+                1 => {}
+                _ => violations.push(Violation::TooManyKings(color)),
             }
+        }
 
-            if let Some(piece) = self.board.get(&idx) {
-                if empty_count != 0 {
-                    rank.push_str(empty_count.to_string().as_str());
-                    empty_count = 0;
+        for idx in 0..SIZE_0X88 as u8 {
+            let Ok(sq) = Square::try_from(idx) else {
+                continue;
+            };
+
+            if let Some(piece) = self.board.get(&sq) {
+                if piece.p_type == PType::PAWN && (sq.rank() == 0 || sq.rank() == 7) {
+                    violations.push(Violation::PawnOnBackRank(sq));
                 }
+            }
+        }
 
-                let p: String = piece.clone().into();
-                rank.push_str(&p);
-            } else {
-                empty_count += 1;
+        // the side that just moved can't have left its own king in check
+        let mut opponent = self.clone();
+        opponent.change_turn();
+        if let Some(king_sq) = opponent.get_current_king_sq() {
+            if opponent.is_attacked(king_sq) {
+                violations.push(Violation::OpponentInCheck);
             }
+        }
 
-            if (idx + 1) % 8 == 0 {
-                if empty_count != 0 {
+        let rights = [
+            (
+                self.castling_rights.white_kingside,
+                Color::WHITE,
+                "e1",
+                "h1",
+            ),
+            (
+                self.castling_rights.white_queenside,
+                Color::WHITE,
+                "e1",
+                "a1",
+            ),
+            (
+                self.castling_rights.black_kingside,
+                Color::BLACK,
+                "e8",
+                "h8",
+            ),
+            (
+                self.castling_rights.black_queenside,
+                Color::BLACK,
+                "e8",
+                "a8",
+            ),
+        ];
+
+        for (has_right, color, king_sq, rook_sq) in rights {
+            if !has_right {
+                continue;
+            }
+
+            let king_sq: Square = king_sq.try_into().unwrap();
+            let rook_sq: Square = rook_sq.try_into().unwrap();
+
+            if self.board.get(&king_sq) != Some(&Piece::new(PType::KING, color))
+                && !violations.contains(&Violation::CastlingWithoutKing(color))
+            {
+                violations.push(Violation::CastlingWithoutKing(color));
+            }
+
+            if self.board.get(&rook_sq) != Some(&Piece::new(PType::ROOK, color)) {
+                violations.push(Violation::CastlingWithoutRook(rook_sq));
+            }
+        }
+
+        if let Some(ep_sq) = self.en_passant_sq {
+            // the pawn that just moved two squares must be in front of the en passant square,
+            // and both the en passant square and the square it came from must be empty
+            let (pawn_delta, origin_delta, pawn_color) = match self.side_to_move {
+                Color::WHITE => (-16, 16, Color::BLACK),
+                Color::BLACK => (16, -16, Color::WHITE),
+            };
+
+            let pawn_present = ep_sq
+                .add(pawn_delta)
+                .is_ok_and(|sq| self.board.get(&sq) == Some(&Piece::new(PType::PAWN, pawn_color)));
+            let origin_empty = ep_sq
+                .add(origin_delta)
+                .is_ok_and(|sq| !self.is_occupied(&sq));
+
+            if !pawn_present || !origin_empty || self.is_occupied(&ep_sq) {
+                violations.push(Violation::InvalidEnPassant(ep_sq));
+            }
+        }
+
+        violations
+    }
+
+    pub(crate) fn full_move_number(&self) -> u16 {
+        self.full_moves
+    }
+
+    // the number of moves in history
+    pub(crate) fn ply(&self) -> usize {
+        self.history.len()
+    }
+
+    pub(crate) fn played_moves(&self) -> Vec<Move> {
+        self.history.iter().map(|entry| entry.m.clone()).collect()
+    }
+
+    // the position the game started from, before any of the moves in history were played
+    pub(crate) fn initial_state(&self) -> GameState {
+        let mut state = self.clone();
+        while !state.history.is_empty() {
+            state.undo();
+        }
+
+        state
+    }
+
+    // TODO refactor this
+    pub fn get_fen(&self) -> String {
+        let mut empty_count: u8 = 0;
+
+        let mut rank = String::new();
+        let mut ranks: Vec<String> = vec![];
+
+        for idx in 0..128 {
+            if utils::is_valid_idx(idx).is_err() {
+                continue;
+            }
+
+            if let Some(piece) = self.board.get(&idx) {
+                if empty_count != 0 {
+                    rank.push_str(empty_count.to_string().as_str());
+                    empty_count = 0;
+                }
+
+                let p: String = piece.clone().into();
+                rank.push_str(&p);
+            } else {
+                empty_count += 1;
+            }
+
+            if (idx + 1) % 8 == 0 {
+                if empty_count != 0 {
                     rank.push_str(empty_count.to_string().as_str());
                     empty_count = 0;
                 }
@@ -885,37 +1288,33 @@ impl GameState {
         };
 
         let half_moves = self.half_moves;
-        let full_moves = if self.has_moved {
-            cmp::max(1, self.full_moves - 1)
-        } else {
-            self.full_moves
-        };
+        let full_moves = self.full_moves;
 
         let mut castling_rights = String::new();
         if self.castling_rights.white_kingside {
-            castling_rights.push_str("K");
+            castling_rights.push('K');
         }
 
         if self.castling_rights.white_queenside {
-            castling_rights.push_str("Q");
+            castling_rights.push('Q');
         }
 
         if self.castling_rights.black_kingside {
-            castling_rights.push_str("k");
+            castling_rights.push('k');
         }
 
         if self.castling_rights.black_queenside {
-            castling_rights.push_str("q")
+            castling_rights.push('q')
         }
 
         if castling_rights.is_empty() {
-            castling_rights.push_str("-");
+            castling_rights.push('-');
         }
 
         let turn: &str = self.side_to_move.into();
 
         // TODO: use format!
-        vec![
+        [
             ranks.join("/"),
             turn.to_string(),
             castling_rights,
@@ -926,84 +1325,363 @@ impl GameState {
         .join(" ")
     }
 
+    #[allow(dead_code)]
     pub fn perft(&mut self, depth: u8, log: bool) -> usize {
         let mut nodes = 0;
-        let mut count = 0;
 
-        if depth <= 0 {
+        if depth == 0 {
             return 1;
         }
 
-        let moves = MoveGen::moves(self, self.side_to_move);
+        let mut moves = MoveList::new();
+        MoveGen::legal_moves(self, self.side_to_move, &mut moves);
 
-        for _move in moves {
-            let from = _move.from.get_notation();
-            let to = _move.to.get_notation();
-
-            match self.play_move(_move.clone()) {
-                Ok(_) => {}
-                Err(e) => {
-                    panic!("{}", e)
-                }
-            }
-            // if self.is_capture(&_move) && depth == 1 {
-            //     self.captures_count += 1;
-            // }
-            // if self.is_in_check && depth == 1 {
-            //     self.check_count += 1;
-            // }
-            count = self.perft(depth - 1, false);
+        for _move in &moves {
+            let undo = self.make_unchecked(_move);
+            let count = self.perft(depth - 1, false);
 
             nodes += count;
 
             if log {
-                if let Some(promotion_piece) = _move.promotion_piece {
-                    match promotion_piece.p_type {
-                        PType::BISHOP => {
-                            println!("{} {}", format!("{}{}b", from, to), count);
-                        }
-                        PType::KNIGHT => {
-                            println!("{} {}", format!("{}{}n", from, to), count);
-                        }
-                        PType::ROOK => {
-                            println!("{} {}", format!("{}{}r", from, to), count);
-                        }
-                        PType::QUEEN => {
-                            println!("{} {}", format!("{}{}q", from, to), count);
-                        }
-                        _ => panic!("invalid promotion piece"),
-                    }
-                } else {
-                    println!("{} {}", format!("{}{}", from, to), count);
-                }
+                println!("{} {}", _move.to_uci(), count);
             }
 
-            self.undo();
+            self.unmake(_move, undo);
         }
 
-        return nodes;
+        nodes
     }
 }
 
 pub struct Chess {
     state: GameState,
+    headers: Vec<(String, String)>,
+    // every move of the game, the first ply of them played on the board and the rest taken
+    // back with undo_move, waiting for redo_move
+    line: Vec<Move>,
+    // a resignation, timeout or agreed draw, which the position can't show
+    ended: Option<Outcome>,
+    // filled in by the first call to outcome after the position changes
+    outcome: OnceLock<Option<Outcome>>,
+}
+
+impl Default for Chess {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Chess {
     pub fn new() -> Self {
-        let default_fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
         let mut state = GameState::new();
-        state.load_fen(default_fen);
+        state
+            .load_fen(DEFAULT_FEN)
+            .expect("the default FEN must be valid");
 
-        Self { state }
+        Self {
+            state,
+            headers: vec![],
+            line: vec![],
+            ended: None,
+            outcome: OnceLock::new(),
+        }
     }
 
+    // playing the move that comes next in the line keeps the rest of it, any other move
+    // starts a new line from here
     pub fn play_move(&mut self, m: Move) -> Result<(), Error> {
-        Ok(self.state.play_move(m)?)
+        self.ensure_playing()?;
+
+        let ply = self.ply();
+        self.state.play_move(m.clone())?;
+        self.outcome.take();
+
+        if self.line.get(ply) != Some(&m) {
+            self.line.truncate(ply);
+            self.line.push(m);
+        }
+
+        Ok(())
+    }
+
+    // a finished game can still be gone through with undo_move and redo_move, but not played
+    // on. after a resignation, timeout or agreed draw that holds for every ply of the line
+    fn ensure_playing(&self) -> Result<(), Error> {
+        if self.ended.is_some() || self.outcome().is_some() {
+            return Err(Error::GameOver);
+        }
+
+        Ok(())
+    }
+
+    // plays the move and presses the clock. if the mover's flag has already fallen the game ends
+    // on time instead, see adjudicate_timeout. the clock isn't rewound by undo_move or go_to_ply
+    pub fn play_move_timed(
+        &mut self,
+        m: Move,
+        clock: &mut GameClock,
+        now: u64,
+    ) -> Result<(), Error> {
+        self.ensure_playing()?;
+
+        if clock.turn() != self.get_turn() {
+            return Err(Error::MustWaitForTurn);
+        }
+
+        if let Some(flagged) = clock.flagged(now) {
+            clock.stop(now);
+            self.timeout(flagged)?;
+            return Err(Error::FlagFell(flagged));
+        }
+
+        self.play_move(m)?;
+        clock.press(now)
+    }
+
+    pub fn undo_move(&mut self) -> Option<Move> {
+        let m = self.state.undo()?;
+        self.outcome.take();
+
+        Some(m)
+    }
+
+    pub fn redo_move(&mut self) -> Option<Move> {
+        let m = self.line.get(self.ply())?.clone();
+        self.state.make_move(m.clone());
+        self.outcome.take();
+
+        Some(m)
+    }
+
+    // the whole line, including any moves after the current ply
+    pub fn history(&self) -> &[Move] {
+        &self.line
+    }
+
+    // how many moves of the line are on the board
+    pub fn ply(&self) -> usize {
+        self.state.ply()
+    }
+
+    pub fn go_to_ply(&mut self, ply: usize) -> Result<(), Error> {
+        if ply > self.line.len() {
+            return Err(Error::InvalidPly(ply));
+        }
+
+        while self.ply() > ply {
+            self.undo_move();
+        }
+        while self.ply() < ply {
+            self.redo_move();
+        }
+
+        Ok(())
+    }
+
+    // the fast path for engines, see GameState::make_unchecked. nothing is recorded in the
+    // history, so undo, repetitions and the PGN don't see these moves
+    pub fn make_unchecked(&mut self, m: &Move) -> Undo {
+        self.outcome.take();
+        self.state.make_unchecked(m)
+    }
+
+    pub fn unmake(&mut self, m: &Move, undo: Undo) {
+        self.outcome.take();
+        self.state.unmake(m, undo)
+    }
+
+    pub fn moves_for_square(&self, sq: Square) -> Vec<Move> {
+        MoveGen::moves_for_square(&self.state, sq)
+    }
+
+    // worked out on demand, the check flag alone is kept up to date as moves are played
+    pub fn check_rays(&self) -> HashSet<Square> {
+        self.state.check_rays()
+    }
+
+    pub fn moves(&self) -> Vec<Move> {
+        MoveGen::moves(&self.state, self.state.side_to_move)
+    }
+
+    pub fn legal_moves(&self) -> LegalMoves<'_> {
+        LegalMoves::new(&self.state)
+    }
+
+    // https://www.chessprogramming.org/Perft
+    pub fn perft(&mut self, depth: u8) -> usize {
+        self.state.perft(depth, false)
+    }
+
+    // the perft count below each legal move, in the form perft diffing tools compare
+    pub fn divide(&mut self, depth: u8) -> Vec<(Move, usize)> {
+        if depth == 0 {
+            return vec![];
+        }
+
+        let moves = MoveGen::moves(&self.state, self.state.side_to_move);
+        let mut counts = Vec::with_capacity(moves.len());
+
+        for m in moves {
+            let undo = self.state.make_unchecked(&m);
+            let count = self.state.perft(depth - 1, false);
+            self.state.unmake(&m, undo);
+
+            counts.push((m, count));
+        }
+
+        counts
+    }
+
+    // splits the root moves across threads, see PerftConfig
+    pub fn perft_with(&self, depth: u8, config: PerftConfig) -> usize {
+        if depth == 0 {
+            return 1;
+        }
+
+        self.divide_with(depth, config)
+            .iter()
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    pub fn divide_with(&self, depth: u8, config: PerftConfig) -> Vec<(Move, usize)> {
+        perft::divide(&self.state, depth, config)
+    }
+
+    pub fn generate_moves(&self, mode: GenMode) -> Vec<Move> {
+        let mut moves = MoveList::new();
+        MoveGen::generate(&self.state, mode, &mut moves);
+
+        moves.to_vec()
+    }
+
+    pub fn move_to_san(&self, m: &Move) -> Result<String, Error> {
+        san::move_to_san(&self.state, m)
+    }
+
+    pub fn parse_san(&self, san: &str) -> Result<Move, Error> {
+        san::parse_san(&self.state, san)
+    }
+
+    pub fn play_san(&mut self, san: &str) -> Result<Move, Error> {
+        self.ensure_playing()?;
+
+        let m = self.parse_san(san)?;
+        self.play_move(m.clone())?;
+
+        Ok(m)
+    }
+
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        match self.headers.iter_mut().find(|(n, _)| n == name) {
+            Some(header) => header.1 = value.to_string(),
+            None => self.headers.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn get_header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn remove_header(&mut self, name: &str) {
+        self.headers.retain(|(n, _)| n != name);
+    }
+
+    // loads the first game of a PGN, replaying its mainline
+    pub fn load_pgn(&mut self, pgn: &str) -> Result<(), Error> {
+        let game = match pgn::PgnReader::new(pgn.as_bytes()).next() {
+            Some(game) => game?,
+            None => {
+                return Err(Error::InvalidPgn {
+                    line: 1,
+                    column: 1,
+                    reason: "no game found".to_string(),
+                })
+            }
+        };
+
+        *self = game.replay()?;
+
+        Ok(())
+    }
+
+    pub fn to_pgn(&self) -> String {
+        pgn::write_pgn(&self.state, &self.headers, self.result())
+    }
+
+    fn result(&self) -> &str {
+        pgn::result(self.outcome(), &self.headers)
+    }
+
+    // None while the game is still going. the position is only looked at once, later calls
+    // return the same outcome until it changes
+    pub fn outcome(&self) -> Option<Outcome> {
+        // a resignation or the like only applies at the end of the line
+        if self.ended.is_some() && self.ply() == self.line.len() {
+            return self.ended;
+        }
+
+        *self.outcome.get_or_init(|| self.state.outcome())
+    }
+
+    pub fn resign(&mut self, color: Color) -> Result<(), Error> {
+        self.end(Outcome::win(color.opponent(), Termination::Resignation))
+    }
+
+    pub fn timeout(&mut self, color: Color) -> Result<(), Error> {
+        self.end(self.adjudicate_timeout(color))
+    }
+
+    // running out of time loses, unless the opponent couldn't mate by any series of legal moves.
+    // this only works the result out, timeout ends the game with it
+    pub fn adjudicate_timeout(&self, flagged: Color) -> Outcome {
+        if self.can_checkmate(flagged.opponent()) && !self.state.is_dead_position() {
+            Outcome::win(flagged.opponent(), Termination::Timeout)
+        } else {
+            Outcome::draw(Termination::Timeout)
+        }
+    }
+
+    pub fn can_checkmate(&self, color: Color) -> bool {
+        self.state.can_checkmate(color)
+    }
+
+    pub fn agree_draw(&mut self) -> Result<(), Error> {
+        self.end(Outcome::draw(Termination::Agreement))
+    }
+
+    // threefold repetition or the fifty-move rule, if either applies to the current position
+    pub fn claimable_draw(&self) -> Option<Termination> {
+        if self.outcome().is_some() {
+            return None;
+        }
+
+        self.state.claimable_draw()
     }
 
-    pub fn moves_for_square(&mut self, sq: Square) -> Vec<Move> {
-        MoveGen::moves_for_square(&mut self.state, sq)
+    pub fn claim_draw(&mut self) -> Result<(), Error> {
+        if self.outcome().is_some() {
+            return Err(Error::GameOver);
+        }
+
+        match self.state.claimable_draw() {
+            Some(termination) => self.end(Outcome::draw(termination)),
+            None => Err(Error::NoDrawToClaim),
+        }
+    }
+
+    fn end(&mut self, outcome: Outcome) -> Result<(), Error> {
+        if self.outcome().is_some() {
+            return Err(Error::GameOver);
+        }
+
+        self.line.truncate(self.ply());
+        self.ended = Some(outcome);
+
+        Ok(())
     }
 
     pub fn get_captures(&self) -> (Vec<Capture>, Vec<Capture>) {
@@ -1021,40 +1699,82 @@ impl Chess {
         (white_captures, black_captures)
     }
 
-    pub fn is_draw(&mut self) -> bool {
-        self.state.is_draw()
+    // drawn by the rules or by agreement, see outcome. a threefold repetition or fifty moves only
+    // count once claimed, see claimable_draw
+    pub fn is_draw(&self) -> bool {
+        self.outcome().is_some_and(|outcome| outcome.is_draw())
     }
 
-    pub fn is_stalemate(&mut self) -> bool {
+    pub fn is_stalemate(&self) -> bool {
         self.state.is_stalemate()
     }
 
-    pub fn is_threefold_repetition(&mut self) -> bool {
+    pub fn is_threefold_repetition(&self) -> bool {
         self.state.is_threefold_repetition()
     }
 
+    pub fn is_fivefold_repetition(&self) -> bool {
+        self.state.is_fivefold_repetition()
+    }
+
     pub fn is_50_moves(&self) -> bool {
         self.state.is_50_moves()
     }
 
+    pub fn is_75_moves(&self) -> bool {
+        self.state.is_75_moves()
+    }
+
     pub fn is_insufficient_material(&self) -> bool {
         self.state.is_insufficient_material()
     }
 
-    pub fn is_checkmate(&mut self) -> bool {
+    pub fn is_dead_position(&self) -> bool {
+        self.state.is_dead_position()
+    }
+
+    pub fn is_checkmate(&self) -> bool {
         self.state.is_checkmate()
     }
 
     pub fn reset(&mut self) {
         self.state.reset();
+        self.line = vec![];
+        self.ended = None;
+        self.outcome.take();
     }
 
     pub fn load_fen(&mut self, fen: &str) -> Result<(), Error> {
-        self.state.load_fen(fen);
+        self.state.load_fen(fen)?;
+        self.line = vec![];
+        self.ended = None;
+        self.outcome.take();
+
+        Ok(())
+    }
+
+    // like load_fen, but also rejects positions that can't occur in a real game
+    pub fn load_fen_strict(&mut self, fen: &str) -> Result<(), Error> {
+        let mut state = GameState::new();
+        state.load_fen(fen)?;
+
+        let violations = state.validate();
+        if !violations.is_empty() {
+            return Err(Error::IllegalPosition(violations));
+        }
+
+        self.state = state;
+        self.line = vec![];
+        self.ended = None;
+        self.outcome.take();
 
         Ok(())
     }
 
+    pub fn validate(&self) -> Vec<Violation> {
+        self.state.validate()
+    }
+
     pub fn get_fen(&self) -> String {
         self.state.get_fen()
     }
@@ -1064,12 +1784,29 @@ impl Chess {
     }
 
     pub fn set_turn(&mut self, color: Color) {
-        self.state.side_to_move = color;
+        if self.state.side_to_move != color {
+            self.state.change_turn();
+            self.line.truncate(self.ply());
+            self.outcome.take();
+        }
+    }
+
+    // Zobrist key of the current position, equal for transposed move orders
+    pub fn hash(&self) -> u64 {
+        self.state.hash()
     }
 
     pub fn get_board_ptr(&self) -> *const Option<Piece> {
         self.state.board.get_board_ptr()
     }
+
+    pub(crate) fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    pub(crate) fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
 }
 
 #[cfg(test)]
@@ -1101,7 +1838,11 @@ mod tests {
 
         assert!(state.is_in_check);
 
-        let mut squares: Vec<String> = state.check_rays.iter().map(|m| m.get_notation()).collect();
+        let mut squares: Vec<String> = state
+            .check_rays()
+            .iter()
+            .map(|m| m.get_notation())
+            .collect();
 
         squares.sort();
         let mut expected = ["d6", "e7", "f8", "b6", "d4", "e3"];
@@ -1117,7 +1858,11 @@ mod tests {
 
         assert!(state.is_in_check);
 
-        let mut squares: Vec<String> = state.check_rays.iter().map(|m| m.get_notation()).collect();
+        let mut squares: Vec<String> = state
+            .check_rays()
+            .iter()
+            .map(|m| m.get_notation())
+            .collect();
 
         squares.sort();
         let mut expected = ["d4", "e3", "b6"];
@@ -1126,6 +1871,35 @@ mod tests {
         assert_eq!(squares, expected);
     }
 
+    #[test]
+    fn uci() {
+        let tests = [
+            ("e2e4", Move::from_str("e2", "e4", None)),
+            ("e1g1", Move::from_str("e1", "g1", None)),
+            (
+                "e7e8q",
+                Move::from_str("e7", "e8", Some(Piece::new(PType::QUEEN, Color::WHITE))),
+            ),
+            (
+                "b2a1n",
+                Move::from_str("b2", "a1", Some(Piece::new(PType::KNIGHT, Color::BLACK))),
+            ),
+        ];
+
+        for (uci, m) in tests {
+            assert_eq!(m.to_uci(), uci);
+            assert_eq!(Move::from_uci(uci).unwrap(), m);
+        }
+
+        assert!(matches!(Move::from_uci("e7e8Q"), Err(Error::InvalidUci)));
+
+        for uci in [
+            "", "e2", "e2e", "e2e9", "i2e4", "e2e4x", "e6e7q", "e7e8k", "e7e8p", "e2e4q1", "é2e4",
+        ] {
+            assert!(Move::from_uci(uci).is_err(), "{}", uci);
+        }
+    }
+
     #[test]
     fn load_fen() {
         let mut state = GameState::new();
@@ -1133,7 +1907,7 @@ mod tests {
         //     "PPPPPPPP/PqPPqPPP/PqPPqPPP/PqrrqPPq/PqPPqPqq/PqPPqqPq/PPPPqPPq/PPPPPPPP w - - 0 1",
         // );
 
-        state.load_fen("8/2b5/1N6/2K3qk/8/8/8/8 w - - 0 1");
+        state.load_fen("8/2b5/1N6/2K3qk/8/8/8/8 w - - 0 1").unwrap();
 
         assert_eq!(
             state
@@ -1154,6 +1928,193 @@ mod tests {
         );
     }
 
+    #[test]
+    fn invalid_fen() {
+        use FenField::*;
+
+        let tests = [
+            ("", Placement),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR", Turn),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+                HalfMoves,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0",
+                FullMoves,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 x",
+                FullMoves,
+            ),
+            ("rnbqkbnr/pppppppp/8/8/8/8/RNBQKBNR w KQkq - 0 1", Placement),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                Placement,
+            ),
+            (
+                "rnbqkbnr/pppppppp/7/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                Placement,
+            ),
+            (
+                "rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                Placement,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/44/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                Placement,
+            ),
+            (
+                "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                Placement,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1",
+                Placement,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+                Turn,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkx - 0 1",
+                Castling,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KKkq - 0 1",
+                Castling,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e4 0 1",
+                EnPassant,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e3 0 1",
+                EnPassant,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq z6 0 1",
+                EnPassant,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1",
+                HalfMoves,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - -1 1",
+                HalfMoves,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 x",
+                FullMoves,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0",
+                FullMoves,
+            ),
+        ];
+
+        let valid_fen = "4k3/8/8/8/5p2/8/4P3/4K3 w - - 0 1";
+        let mut state = GameState::new();
+        state.load_fen(valid_fen).unwrap();
+
+        for (fen, expected) in tests {
+            match state.load_fen(fen) {
+                Err(Error::InvalidFen { field, .. }) => assert_eq!(field, expected, "{}", fen),
+                _ => panic!("expected an invalid FEN error for {}", fen),
+            }
+
+            // a failed load must leave the previous position untouched
+            assert_eq!(state.get_fen(), valid_fen);
+        }
+
+        let mut chess = Chess::new();
+        assert!(chess.load_fen("8/8/8/8/8/8/8/8 w - - 0").is_err());
+        assert_eq!(
+            chess.get_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+        );
+    }
+
+    #[test]
+    fn validate() {
+        let tests = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                vec![],
+            ),
+            ("r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1", vec![]),
+            (
+                "4k3/8/8/8/8/8/8/8 w - - 0 1",
+                vec![Violation::MissingKing(Color::WHITE)],
+            ),
+            (
+                "4k3/8/8/8/8/8/8/2K1K3 w - - 0 1",
+                vec![Violation::TooManyKings(Color::WHITE)],
+            ),
+            (
+                "P3k3/8/8/8/8/8/8/4K2p w - - 0 1",
+                vec![
+                    Violation::PawnOnBackRank("h1".try_into().unwrap()),
+                    Violation::PawnOnBackRank("a8".try_into().unwrap()),
+                ],
+            ),
+            ("4k3/8/8/8/8/8/8/4K2R b - - 0 1", vec![]),
+            (
+                "4k2R/8/8/8/8/8/8/4K3 w - - 0 1",
+                vec![Violation::OpponentInCheck],
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K2R w KQq - 0 1",
+                vec![
+                    Violation::CastlingWithoutRook("a1".try_into().unwrap()),
+                    Violation::CastlingWithoutRook("a8".try_into().unwrap()),
+                ],
+            ),
+            (
+                "r3k2r/8/8/8/8/8/8/R4K1R w KQkq - 0 1",
+                vec![Violation::CastlingWithoutKing(Color::WHITE)],
+            ),
+            (
+                "4k3/8/8/4P3/8/8/8/4K3 w - d6 0 1",
+                vec![Violation::InvalidEnPassant("d6".try_into().unwrap())],
+            ),
+            (
+                "4k3/3p4/8/3pP3/8/8/8/4K3 w - d6 0 1",
+                vec![Violation::InvalidEnPassant("d6".try_into().unwrap())],
+            ),
+        ];
+
+        for (fen, expected) in tests {
+            let mut state = GameState::new();
+            state.load_fen(fen).unwrap();
+
+            assert_eq!(state.validate(), expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn load_fen_strict() {
+        let mut chess = Chess::new();
+
+        assert!(matches!(
+            chess.load_fen_strict("4k3/8/8/8/8/8/8/8 w - - 0 1"),
+            Err(Error::IllegalPosition(_))
+        ));
+        assert_eq!(
+            chess.get_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+        );
+
+        assert!(chess.load_fen("4k3/8/8/8/8/8/8/8 w - - 0 1").is_ok());
+        assert_eq!(chess.validate().len(), 1);
+
+        assert!(chess
+            .load_fen_strict("4k3/8/8/8/8/8/8/4K3 b - - 0 1")
+            .is_ok());
+        assert!(chess.validate().is_empty());
+    }
+
     #[test]
     fn get_fen() {
         let mut state = GameState::new();
@@ -1168,7 +2129,7 @@ mod tests {
         ];
 
         for fen in fens {
-            state.load_fen(fen);
+            state.load_fen(fen).unwrap();
 
             assert_eq!(fen, state.get_fen());
 
@@ -1176,7 +2137,7 @@ mod tests {
         }
 
         let mut state = GameState::new();
-        state.load_fen("4k3/8/8/8/5p2/8/4P3/4K3 w - - 0 1");
+        state.load_fen("4k3/8/8/8/5p2/8/4P3/4K3 w - - 0 1").unwrap();
         assert!(state.play_move(Move::from_str("e2", "e4", None)).is_ok());
         assert_eq!(state.get_fen(), "4k3/8/8/8/4Pp2/8/8/4K3 b - e3 0 1");
 
@@ -1253,7 +2214,7 @@ mod tests {
 
         for test in tests {
             state.reset();
-            state.load_fen(&test.fen);
+            state.load_fen(&test.fen).unwrap();
 
             for m in test.moves {
                 assert!(state.play_move(m).is_ok());
@@ -1290,7 +2251,7 @@ mod tests {
 
         for test in tests {
             state.reset();
-            state.load_fen(&test.fen);
+            state.load_fen(&test.fen).unwrap();
 
             for m in test.moves {
                 assert!(state.play_move(m).is_ok());
@@ -1324,7 +2285,7 @@ mod tests {
 
         for test in tests {
             state.reset();
-            state.load_fen(&test.fen);
+            state.load_fen(&test.fen).unwrap();
 
             for m in test.moves {
                 assert!(state.play_move(m).is_ok());
@@ -1346,7 +2307,7 @@ mod tests {
 
         for fen in fens {
             let mut state = GameState::new();
-            state.load_fen(fen);
+            state.load_fen(fen).unwrap();
 
             assert!(state.is_checkmate());
         }
@@ -1365,7 +2326,7 @@ mod tests {
 
         for fen in fens {
             let mut state = GameState::new();
-            state.load_fen(fen);
+            state.load_fen(fen).unwrap();
 
             assert!(state.is_insufficient_material());
         }
@@ -1373,13 +2334,15 @@ mod tests {
         let fens = vec![
             "8/2p5/8/8/8/8/8/k6K w - - 0 1",
             "5k1K/7B/8/6b1/8/8/8/8 b - - 0 1",
+            "8/8/8/8/8/8/2NN4/k6K w - - 0 1",
+            "8/b7/2B5/3B4/8/8/8/k6K w - - 0 1",
             "7K/5k1N/8/6b1/8/8/8/8 b - - 0 1",
             "7K/5k1N/8/4n3/8/8/8/8 b - - 0 1",
         ];
 
         for fen in fens {
             let mut state = GameState::new();
-            state.load_fen(fen);
+            state.load_fen(fen).unwrap();
 
             assert!(!state.is_insufficient_material());
         }
@@ -1395,13 +2358,13 @@ mod tests {
 
         for fen in fens {
             let mut state = GameState::new();
-            state.load_fen(fen);
+            state.load_fen(fen).unwrap();
 
             assert!(state.is_stalemate());
         }
 
         let mut state = GameState::new();
-        state.load_fen("R3k3/8/4K3/8/8/8/8/8 b - - 0 1");
+        state.load_fen("R3k3/8/4K3/8/8/8/8/8 b - - 0 1").unwrap();
 
         assert!(!state.is_stalemate());
     }
@@ -1409,7 +2372,9 @@ mod tests {
     #[test]
     fn threefold_repetition() {
         let mut state = GameState::new();
-        state.load_fen("8/pp3p1k/2p2q1p/3r1P2/5R2/7P/P1P1QP2/7K b - - 2 30");
+        state
+            .load_fen("8/pp3p1k/2p2q1p/3r1P2/5R2/7P/P1P1QP2/7K b - - 2 30")
+            .unwrap();
         let moves = vec![
             Move::from_str("f6", "e5", None),
             Move::from_str("e2", "h5", None),
@@ -1428,57 +2393,477 @@ mod tests {
         assert!(state.is_threefold_repetition());
     }
 
-    // #[test]
-    fn perft() {
-        struct Perft {
-            fen: &'static str,
-            depth: u8,
-            expected: usize,
+    #[test]
+    fn outcome() {
+        let outcome = |fen: &str| {
+            let mut chess = Chess::new();
+            chess.load_fen(fen).unwrap();
+            chess.outcome()
+        };
+
+        assert_eq!(outcome(DEFAULT_FEN), None);
+        assert_eq!(
+            outcome("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3"),
+            Some(Outcome::win(Color::BLACK, Termination::Checkmate))
+        );
+        assert_eq!(
+            outcome("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"),
+            Some(Outcome::draw(Termination::Stalemate))
+        );
+        assert_eq!(
+            outcome("8/8/4k3/8/8/3BK3/8/8 w - - 0 1"),
+            Some(Outcome::draw(Termination::InsufficientMaterial))
+        );
+        assert_eq!(
+            outcome("4k3/8/8/p1p1p1p1/P1P1P1P1/8/8/4K3 w - - 0 1"),
+            Some(Outcome::draw(Termination::DeadPosition))
+        );
+        assert_eq!(outcome("8/8/4k3/8/8/3RK3/8/8 w - - 100 80"), None);
+        assert_eq!(
+            outcome("8/8/4k3/8/8/3RK3/8/8 w - - 150 80"),
+            Some(Outcome::draw(Termination::SeventyFiveMoves))
+        );
+
+        // mate on the last half move still wins
+        assert_eq!(
+            outcome("7k/6Q1/6K1/8/8/8/8/8 b - - 150 80").map(|o| o.termination),
+            Some(Termination::Checkmate)
+        );
+
+        let mut chess = Chess::new();
+        for _ in 0..2 {
+            for san in ["Nf3", "Nf6", "Ng1", "Ng8"] {
+                chess.play_san(san).unwrap();
+            }
         }
+        // a threefold repetition has to be claimed
+        assert_eq!(chess.outcome(), None);
+        assert!(!chess.is_draw());
+        for _ in 0..2 {
+            for san in ["Nf3", "Nf6", "Ng1", "Ng8"] {
+                chess.play_san(san).unwrap();
+            }
+        }
+        assert_eq!(
+            chess.outcome(),
+            Some(Outcome::draw(Termination::FivefoldRepetition))
+        );
+        assert!(chess.is_draw());
+        assert!(chess.to_pgn().trim_end().ends_with("1/2-1/2"));
+        assert!(matches!(chess.play_san("Nf3"), Err(Error::GameOver)));
+
+        chess
+            .load_fen("8/8/4k3/8/8/3RK3/8/8 w - - 149 120")
+            .unwrap();
+        assert!(!chess.is_draw());
+        chess.play_san("Rd1").unwrap();
+        assert_eq!(
+            chess.outcome(),
+            Some(Outcome::draw(Termination::SeventyFiveMoves))
+        );
+        assert!(chess.is_draw());
+        assert!(matches!(chess.play_san("Ke5"), Err(Error::GameOver)));
 
-        let tests = [
-            Perft {
-                fen: "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
-                depth: 3,
-                expected: 2812,
-            },
-            Perft {
-                fen: "rnbqkbnr/p3pppp/2p5/1pPp4/3P4/8/PP2PPPP/RNBQKBNR w KQkq b6 0 4",
-                depth: 3,
-                expected: 23509,
-            },
-            Perft {
-                fen: "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 101",
-                depth: 3,
-                expected: 89890,
-            },
-            Perft {
-                fen: "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
-                depth: 3,
-                expected: 62379,
-            },
-            Perft {
-                fen: "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
-                depth: 4,
-                expected: 422333,
-            },
-            Perft {
-                fen: "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
-                depth: 3,
-                expected: 97862,
-            },
-            Perft {
-                fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
-                depth: 4,
-                expected: 197281,
-            },
-        ];
+        // set from outside, and only while the game is still going
+        let mut chess = Chess::new();
+        chess.resign(Color::WHITE).unwrap();
+        assert_eq!(
+            chess.outcome(),
+            Some(Outcome::win(Color::BLACK, Termination::Resignation))
+        );
+        assert!(matches!(chess.agree_draw(), Err(Error::GameOver)));
+        assert!(matches!(chess.play_san("e4"), Err(Error::GameOver)));
+        assert!(matches!(
+            chess.play_move(Move::from_str("e2", "e4", None)),
+            Err(Error::GameOver)
+        ));
+        assert_eq!(
+            chess.outcome(),
+            Some(Outcome::win(Color::BLACK, Termination::Resignation))
+        );
+        chess.load_fen(DEFAULT_FEN).unwrap();
+        assert_eq!(chess.outcome(), None);
+
+        let mut chess = Chess::new();
+        chess.play_san("e4").unwrap();
+        chess.agree_draw().unwrap();
+        assert!(chess.to_pgn().trim_end().ends_with("1/2-1/2"));
+
+        // going back through the game is fine, branching off from it isn't
+        chess.undo_move().unwrap();
+        assert!(matches!(chess.play_san("d4"), Err(Error::GameOver)));
+        assert_eq!(chess.redo_move(), Some(Move::from_str("e2", "e4", None)));
+        assert_eq!(chess.outcome(), Some(Outcome::draw(Termination::Agreement)));
+
+        let mut chess = Chess::new();
+        chess.load_fen("8/8/4k3/8/8/4K3/8/7r w - - 0 1").unwrap();
+        chess.timeout(Color::WHITE).unwrap();
+        assert_eq!(
+            chess.outcome(),
+            Some(Outcome::win(Color::BLACK, Termination::Timeout))
+        );
+        assert!(matches!(chess.play_san("Kd2"), Err(Error::GameOver)));
+
+        // white has only the king left, so black running out of time can't lose
+        chess.load_fen("8/8/4k3/8/8/4K3/8/7r w - - 0 1").unwrap();
+        chess.timeout(Color::BLACK).unwrap();
+        assert_eq!(chess.outcome(), Some(Outcome::draw(Termination::Timeout)));
+    }
+
+    #[test]
+    fn is_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Chess>();
+    }
+
+    #[test]
+    fn timeout() {
+        let adjudicate = |fen: &str, flagged: Color| {
+            let mut chess = Chess::new();
+            chess.load_fen(fen).unwrap();
+            chess.adjudicate_timeout(flagged)
+        };
+        let loss = |flagged: Color| Outcome::win(flagged.opponent(), Termination::Timeout);
+        let draw = Outcome::draw(Termination::Timeout);
+
+        // nobody can get at the locked pawns, so the position is dead
+        assert_eq!(
+            adjudicate("4k3/8/8/p1p1p1p1/P1P1P1P1/8/8/4K3 w - - 0 1", Color::BLACK),
+            draw
+        );
+
+        // the lone knight can only mate with a piece of the other side in the way
+        assert_eq!(
+            adjudicate("8/8/4k3/8/8/4K3/8/7N b - - 0 1", Color::BLACK),
+            draw
+        );
+        assert_eq!(
+            adjudicate("8/8/4k3/4p3/8/4K3/8/7N b - - 0 1", Color::BLACK),
+            loss(Color::BLACK)
+        );
+        assert_eq!(
+            adjudicate("8/8/4k3/4p3/8/4K3/8/7N w - - 0 1", Color::WHITE),
+            loss(Color::WHITE)
+        );
+
+        // bishops of one square color need something on the other color to mate against
+        assert_eq!(
+            adjudicate("8/8/4k3/3b4/8/4K3/8/7B b - - 0 1", Color::BLACK),
+            draw
+        );
+        assert_eq!(
+            adjudicate("8/8/4k3/4b3/8/4K3/8/7B b - - 0 1", Color::BLACK),
+            loss(Color::BLACK)
+        );
+        assert_eq!(
+            adjudicate("8/8/4k3/8/8/4K3/8/5BB1 b - - 0 1", Color::BLACK),
+            loss(Color::BLACK)
+        );
+        assert_eq!(
+            adjudicate("8/8/4k3/8/8/4K3/8/6NN b - - 0 1", Color::BLACK),
+            loss(Color::BLACK)
+        );
+        assert_eq!(
+            adjudicate("8/8/4k3/8/8/4K3/8/7R w - - 0 1", Color::WHITE),
+            draw
+        );
+
+        let mut chess = Chess::new();
+        chess.load_fen("8/8/4k3/4p3/8/4K3/8/7N w - - 0 1").unwrap();
+        assert!(chess.can_checkmate(Color::WHITE));
+        assert!(chess.can_checkmate(Color::BLACK));
+        chess.timeout(Color::BLACK).unwrap();
+        assert_eq!(chess.outcome(), Some(loss(Color::BLACK)));
+    }
+
+    #[test]
+    fn play_move_timed() {
+        use crate::clock::{TimeControl, Timing};
+
+        let mut chess = Chess::new();
+        let mut clock = GameClock::new(TimeControl::new(1000, Timing::Fischer(500)), Color::WHITE);
+        let mv = |from: &str, to: &str| Move::from_str(from, to, None);
+
+        // the first move starts black's clock
+        chess
+            .play_move_timed(mv("e2", "e4"), &mut clock, 0)
+            .unwrap();
+        chess
+            .play_move_timed(mv("e7", "e5"), &mut clock, 400)
+            .unwrap();
+        assert_eq!(clock.remaining(Color::BLACK, 400), 1100);
+
+        // an illegal move leaves the clock running
+        assert!(chess
+            .play_move_timed(mv("e4", "e5"), &mut clock, 500)
+            .is_err());
+        assert_eq!(clock.turn(), Color::WHITE);
+        assert_eq!(clock.remaining(Color::WHITE, 500), 900);
+
+        assert!(matches!(
+            chess.play_move_timed(mv("g1", "f3"), &mut clock, 1400),
+            Err(Error::FlagFell(Color::WHITE))
+        ));
+        assert_eq!(chess.ply(), 2);
+        assert_eq!(
+            chess.outcome(),
+            Some(Outcome::win(Color::BLACK, Termination::Timeout))
+        );
+    }
+
+    #[test]
+    fn history_navigation() {
+        let mut chess = Chess::new();
+        assert_eq!(chess.undo_move(), None);
+
+        for san in ["e4", "e5", "Nf3", "Nc6"] {
+            chess.play_san(san).unwrap();
+        }
+        let fen = chess.get_fen();
+        let line = chess.history().to_vec();
+
+        assert_eq!(chess.undo_move(), Some(Move::from_str("b8", "c6", None)));
+        assert_eq!(chess.undo_move(), Some(Move::from_str("g1", "f3", None)));
+        assert_eq!(chess.ply(), 2);
+        assert_eq!(chess.history(), line);
+        assert_eq!(
+            chess.get_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2"
+        );
+
+        assert_eq!(chess.redo_move(), Some(Move::from_str("g1", "f3", None)));
+        assert_eq!(chess.redo_move(), Some(Move::from_str("b8", "c6", None)));
+        assert_eq!(chess.redo_move(), None);
+        assert_eq!(chess.get_fen(), fen);
+
+        chess.go_to_ply(0).unwrap();
+        assert_eq!(chess.get_fen(), DEFAULT_FEN);
+        chess.go_to_ply(4).unwrap();
+        assert_eq!(chess.get_fen(), fen);
+        assert!(matches!(chess.go_to_ply(5), Err(Error::InvalidPly(5))));
+
+        // replaying the next move keeps the line, a different one replaces the rest of it
+        chess.go_to_ply(2).unwrap();
+        chess.play_san("Nf3").unwrap();
+        assert_eq!(chess.history().len(), 4);
+        chess.play_san("Nf6").unwrap();
+        assert_eq!(chess.history().len(), 4);
+        assert_eq!(chess.history()[3], Move::from_str("g8", "f6", None));
+        assert_eq!(chess.redo_move(), None);
+
+        // a resignation stands at the end of the line, not while going back over it
+        chess.resign(Color::BLACK).unwrap();
+        chess.go_to_ply(1).unwrap();
+        assert_eq!(chess.outcome(), None);
+        chess.go_to_ply(4).unwrap();
+        assert_eq!(
+            chess.outcome(),
+            Some(Outcome::win(Color::WHITE, Termination::Resignation))
+        );
+    }
+
+    #[test]
+    fn claim_draw() {
+        let mut chess = Chess::new();
+        assert_eq!(chess.claimable_draw(), None);
+        assert!(matches!(chess.claim_draw(), Err(Error::NoDrawToClaim)));
+
+        for _ in 0..2 {
+            for san in ["Nf3", "Nf6", "Ng1", "Ng8"] {
+                chess.play_san(san).unwrap();
+            }
+        }
+        assert_eq!(
+            chess.claimable_draw(),
+            Some(Termination::ThreefoldRepetition)
+        );
+        chess.claim_draw().unwrap();
+        assert_eq!(
+            chess.outcome(),
+            Some(Outcome::draw(Termination::ThreefoldRepetition))
+        );
+        assert_eq!(chess.claimable_draw(), None);
+
+        chess.load_fen("8/8/4k3/8/8/3RK3/8/8 w - - 99 80").unwrap();
+        assert_eq!(chess.claimable_draw(), None);
+        chess.play_san("Rd1").unwrap();
+        assert!(chess.is_50_moves() && !chess.is_75_moves());
+        chess.claim_draw().unwrap();
+        assert_eq!(
+            chess.outcome(),
+            Some(Outcome::draw(Termination::FiftyMoves))
+        );
+
+        // the automatic rule needs no claim
+        chess.load_fen("8/8/4k3/8/8/3RK3/8/8 w - - 149 80").unwrap();
+        chess.play_san("Rd1").unwrap();
+        assert!(chess.is_75_moves());
+        assert!(matches!(chess.claim_draw(), Err(Error::GameOver)));
+    }
+
+    #[test]
+    fn move_clocks() {
+        let mut chess = Chess::new();
+        for san in ["e4", "e5", "Nf3"] {
+            chess.play_san(san).unwrap();
+        }
+        assert_eq!(
+            chess.get_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+
+        // more than a u8 could count, still short of the seventy-five-move rule
+        chess
+            .load_fen("8/8/4k3/8/8/3RK3/8/8 w - - 140 300")
+            .unwrap();
+        for san in ["Rd1", "Ke5", "Rd3", "Kf5"] {
+            chess.play_san(san).unwrap();
+        }
+        assert_eq!(chess.get_fen(), "8/8/8/5k2/8/3RK3/8/8 w - - 144 302");
+        assert_eq!(chess.outcome(), None);
+    }
+
+    #[test]
+    fn repetition_rules() {
+        let play = |state: &mut GameState, moves: &[(&str, &str)]| {
+            for (from, to) in moves {
+                state.play_move(Move::from_str(from, to, None)).unwrap();
+            }
+        };
+        let king_walk = [("e1", "f1"), ("e8", "f8"), ("f1", "e1"), ("f8", "e8")];
+
+        // the first position still had castling rights, so it doesn't count
         let mut state = GameState::new();
+        state
+            .load_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1")
+            .unwrap();
+        play(&mut state, &king_walk);
+        play(&mut state, &king_walk);
+        assert_eq!(state.repetition_count(), 2);
+        assert!(!state.is_threefold_repetition());
+        play(&mut state, &king_walk);
+        assert!(state.is_threefold_repetition());
+        assert!(!state.is_fivefold_repetition());
+        play(&mut state, &king_walk);
+        play(&mut state, &king_walk);
+        assert!(state.is_fivefold_repetition());
 
-        for test in tests {
-            state.reset();
-            state.load_fen(&test.fen);
-            assert_eq!(state.perft(test.depth, false), test.expected);
+        let king_shuffle = [("a4", "a5"), ("e1", "e2"), ("a5", "a4"), ("e2", "e1")];
+
+        // dxe3 would expose the black king, so the en passant square doesn't count
+        let mut state = GameState::new();
+        state.load_fen("8/8/8/8/k2pP2R/8/8/4K3 b - e3 0 1").unwrap();
+        play(&mut state, &king_shuffle);
+        play(&mut state, &king_shuffle);
+        assert!(state.is_threefold_repetition());
+
+        // here dxe3 is legal, so the first position is a different one
+        let mut state = GameState::new();
+        state.load_fen("8/8/8/8/k2pP3/8/8/4K2R b - e3 0 1").unwrap();
+        play(&mut state, &king_shuffle);
+        play(&mut state, &king_shuffle);
+        assert!(!state.is_threefold_repetition());
+    }
+
+    #[test]
+    fn make_unmake() {
+        let fens = [
+            DEFAULT_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        ];
+
+        // every legal move must be fully taken back, and leave the same position play_move does
+        for fen in fens {
+            let mut state = GameState::new();
+            state.load_fen(fen).unwrap();
+
+            for m in MoveGen::moves(&state, state.side_to_move) {
+                let mut played = state.clone();
+                played.play_move(m.clone()).unwrap();
+
+                let undo = state.make_unchecked(&m);
+                assert_eq!(state.get_fen(), played.get_fen());
+                assert_eq!(state.hash(), state.compute_hash());
+                assert_eq!(state.is_in_check, played.is_in_check);
+
+                state.unmake(&m, undo);
+                assert_eq!(state.get_fen(), fen);
+                assert_eq!(state.hash(), state.compute_hash());
+            }
+        }
+    }
+
+    #[test]
+    fn zobrist_hash() {
+        let fens = [
+            DEFAULT_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        ];
+
+        // every legal move and its undo must keep the incremental hash in sync
+        for fen in fens {
+            let mut state = GameState::new();
+            state.load_fen(fen).unwrap();
+            let before = state.hash();
+            let side = state.side_to_move;
+
+            for m in MoveGen::moves(&state, side) {
+                state.make_move(m);
+                assert_eq!(state.hash(), state.compute_hash());
+
+                state.undo();
+                assert_eq!(state.hash(), before);
+            }
+        }
+
+        // transpositions reach the same hash
+        let mut a = Chess::new();
+        let mut b = Chess::new();
+        for (from, to) in [("g1", "f3"), ("g8", "f6"), ("b1", "c3")] {
+            a.play_move(Move::from_str(from, to, None)).unwrap();
+        }
+        for (from, to) in [("b1", "c3"), ("g8", "f6"), ("g1", "f3")] {
+            b.play_move(Move::from_str(from, to, None)).unwrap();
         }
+        assert_eq!(a.hash(), b.hash());
+
+        // the side to move, castling rights and en passant square are part of the key
+        let mut c = Chess::new();
+        c.load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1")
+            .unwrap();
+        assert_ne!(c.hash(), Chess::new().hash());
+        c.load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Kkq - 0 1")
+            .unwrap();
+        assert_ne!(c.hash(), Chess::new().hash());
+        c.load_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1")
+            .unwrap();
+        let with_ep = c.hash();
+        c.load_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+            .unwrap();
+        assert_ne!(with_ep, c.hash());
+    }
+
+    #[test]
+    fn divide() {
+        let mut chess = Chess::new();
+        chess
+            .load_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+
+        let divide = chess.divide(2);
+        let castle = divide
+            .iter()
+            .find(|(m, _)| m.to_uci() == "e1g1")
+            .expect("castling must be in the divide");
+
+        assert_eq!(divide.len(), 48);
+        assert_eq!(castle.1, 43);
+        assert_eq!(divide.iter().map(|(_, count)| count).sum::<usize>(), 2039);
+        assert_eq!(chess.perft(2), 2039);
+        assert!(chess.divide(0).is_empty());
     }
 }
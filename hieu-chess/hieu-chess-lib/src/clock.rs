@@ -0,0 +1,364 @@
+use crate::chess::Color;
+use crate::error::Error;
+
+// what a player gets back for each move. all times are in milliseconds
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Timing {
+    // sudden death
+    None,
+    // the increment is added after every move
+    Fischer(u64),
+    // the time used is given back after the move, up to the delay
+    Bronstein(u64),
+    // US delay, the clock only starts counting down once the delay has passed
+    Delay(u64),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Stage {
+    // moves to make in this stage, None for the rest of the game
+    pub moves: Option<u16>,
+    // added to the clock when the stage starts
+    pub time: u64,
+    pub timing: Timing,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TimeControl {
+    stages: Vec<Stage>,
+}
+
+impl TimeControl {
+    // a single stage for the whole game, e.g. 5 minutes + 3 seconds
+    pub fn new(time: u64, timing: Timing) -> Self {
+        Self {
+            stages: vec![Stage {
+                moves: None,
+                time,
+                timing,
+            }],
+        }
+    }
+
+    // every stage but the last needs a move count. a last stage with a move count repeats, like
+    // 40/7200 in a PGN TimeControl header
+    pub fn with_stages(stages: Vec<Stage>) -> Result<Self, Error> {
+        let Some((last, rest)) = stages.split_last() else {
+            return Err(Error::InvalidTimeControl);
+        };
+
+        if last.moves == Some(0) || rest.iter().any(|s| s.moves.is_none_or(|n| n == 0)) {
+            return Err(Error::InvalidTimeControl);
+        }
+
+        Ok(Self { stages })
+    }
+
+    pub fn stages(&self) -> &[Stage] {
+        &self.stages
+    }
+
+    fn stage(&self, idx: usize) -> &Stage {
+        &self.stages[idx.min(self.stages.len() - 1)]
+    }
+}
+
+fn side(color: Color) -> usize {
+    match color {
+        Color::WHITE => 0,
+        Color::BLACK => 1,
+    }
+}
+
+// a two-sided clock. it never reads the time itself, every call takes the current timestamp
+// in milliseconds, so the caller decides where time comes from
+#[derive(Clone, Debug)]
+pub struct GameClock {
+    control: TimeControl,
+    remaining: [u64; 2],
+    stage: [usize; 2],
+    // moves made in the current stage
+    stage_moves: [u16; 2],
+    turn: Color,
+    // when the side to move's clock was started, None while stopped
+    started: Option<u64>,
+    flagged: Option<Color>,
+}
+
+impl GameClock {
+    // the clock starts stopped, see start and press
+    pub fn new(control: TimeControl, turn: Color) -> Self {
+        let time = control.stage(0).time;
+
+        Self {
+            control,
+            remaining: [time; 2],
+            stage: [0; 2],
+            stage_moves: [0; 2],
+            turn,
+            started: None,
+            flagged: None,
+        }
+    }
+
+    pub fn control(&self) -> &TimeControl {
+        &self.control
+    }
+
+    // whose clock is, or would be, running
+    pub fn turn(&self) -> Color {
+        self.turn
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started.is_some()
+    }
+
+    pub fn start(&mut self, now: u64) {
+        if self.flagged.is_none() {
+            self.started.get_or_insert(now);
+        }
+    }
+
+    // takes the time used so far off the side to move, a delay starts over once restarted
+    pub fn stop(&mut self, now: u64) {
+        if !self.is_running() {
+            return;
+        }
+
+        let turn = side(self.turn);
+        self.flagged = self.flagged(now);
+        self.remaining[turn] = self.remaining[turn].saturating_sub(self.used(now));
+        self.started = None;
+    }
+
+    pub fn remaining(&self, color: Color, now: u64) -> u64 {
+        let time = self.remaining[side(color)];
+
+        if color == self.turn {
+            time.saturating_sub(self.used(now))
+        } else {
+            time
+        }
+    }
+
+    // the side whose time ran out, if any
+    pub fn flagged(&self, now: u64) -> Option<Color> {
+        if self.flagged.is_some() {
+            return self.flagged;
+        }
+
+        if self.is_running() && self.remaining(self.turn, now) == 0 {
+            Some(self.turn)
+        } else {
+            None
+        }
+    }
+
+    // ends the turn of the side to move and starts the other clock. pressing a stopped clock
+    // neither takes time off nor adds any, so the first press can start the game, but the move
+    // still counts towards the stage
+    pub fn press(&mut self, now: u64) -> Result<(), Error> {
+        if let Some(flagged) = self.flagged(now) {
+            self.stop(now);
+            return Err(Error::FlagFell(flagged));
+        }
+
+        let turn = side(self.turn);
+        let stage = *self.control.stage(self.stage[turn]);
+
+        if let Some(started) = self.started {
+            let elapsed = now.saturating_sub(started);
+
+            self.remaining[turn] -= self.used(now);
+            self.remaining[turn] += match stage.timing {
+                Timing::Fischer(increment) => increment,
+                Timing::Bronstein(delay) => elapsed.min(delay),
+                Timing::None | Timing::Delay(_) => 0,
+            };
+        }
+
+        self.stage_moves[turn] += 1;
+        if stage.moves == Some(self.stage_moves[turn]) {
+            self.stage[turn] += 1;
+            self.stage_moves[turn] = 0;
+            self.remaining[turn] += self.control.stage(self.stage[turn]).time;
+        }
+
+        self.turn = self.turn.opponent();
+        self.started = Some(now);
+
+        Ok(())
+    }
+
+    // time taken off the clock of the side to move, counting the delay out
+    fn used(&self, now: u64) -> u64 {
+        let Some(started) = self.started else {
+            return 0;
+        };
+
+        let elapsed = now.saturating_sub(started);
+
+        match self.control.stage(self.stage[side(self.turn)]).timing {
+            Timing::Delay(delay) => elapsed.saturating_sub(delay),
+            _ => elapsed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = 1000;
+    const MINUTE: u64 = 60 * SECOND;
+
+    #[test]
+    fn increments_and_delays() {
+        let play = |timing: Timing, think: u64| {
+            let mut clock = GameClock::new(TimeControl::new(MINUTE, timing), Color::WHITE);
+            clock.start(0);
+            clock.press(think).unwrap();
+            clock.remaining(Color::WHITE, think)
+        };
+
+        assert_eq!(play(Timing::None, 5 * SECOND), 55 * SECOND);
+        assert_eq!(play(Timing::Fischer(2 * SECOND), 5 * SECOND), 57 * SECOND);
+        assert_eq!(play(Timing::Fischer(2 * SECOND), SECOND), 61 * SECOND);
+
+        // the delay is never more than the time used, so the clock can't go up
+        assert_eq!(play(Timing::Bronstein(2 * SECOND), 5 * SECOND), 57 * SECOND);
+        assert_eq!(play(Timing::Bronstein(2 * SECOND), SECOND), MINUTE);
+        assert_eq!(play(Timing::Delay(2 * SECOND), 5 * SECOND), 57 * SECOND);
+        assert_eq!(play(Timing::Delay(2 * SECOND), SECOND), MINUTE);
+
+        // the simple delay holds the clock still while it runs
+        let mut clock = GameClock::new(
+            TimeControl::new(MINUTE, Timing::Delay(2 * SECOND)),
+            Color::WHITE,
+        );
+        clock.start(0);
+        assert_eq!(clock.remaining(Color::WHITE, SECOND), MINUTE);
+        assert_eq!(clock.remaining(Color::WHITE, 3 * SECOND), 59 * SECOND);
+        assert_eq!(clock.remaining(Color::BLACK, 3 * SECOND), MINUTE);
+    }
+
+    #[test]
+    fn stages() {
+        // 40 moves in 90 minutes, then 30 minutes for the rest, with 30 seconds a move
+        let control = TimeControl::with_stages(vec![
+            Stage {
+                moves: Some(40),
+                time: 90 * MINUTE,
+                timing: Timing::Fischer(30 * SECOND),
+            },
+            Stage {
+                moves: None,
+                time: 30 * MINUTE,
+                timing: Timing::Fischer(30 * SECOND),
+            },
+        ])
+        .unwrap();
+        let mut clock = GameClock::new(control, Color::WHITE);
+        let mut now = 0;
+
+        clock.start(now);
+        for _ in 0..39 * 2 {
+            now += MINUTE;
+            clock.press(now).unwrap();
+        }
+        assert_eq!(
+            clock.remaining(Color::WHITE, now),
+            90 * MINUTE - 39 * 30 * SECOND
+        );
+
+        // the 40th move brings in the second stage
+        now += MINUTE;
+        clock.press(now).unwrap();
+        assert_eq!(
+            clock.remaining(Color::WHITE, now),
+            90 * MINUTE - 40 * 30 * SECOND + 30 * MINUTE
+        );
+        assert_eq!(
+            clock.remaining(Color::BLACK, now),
+            90 * MINUTE - 39 * 30 * SECOND
+        );
+
+        // a repeating last stage
+        let control = TimeControl::with_stages(vec![Stage {
+            moves: Some(2),
+            time: MINUTE,
+            timing: Timing::None,
+        }])
+        .unwrap();
+        let mut clock = GameClock::new(control, Color::WHITE);
+
+        clock.start(0);
+        for now in 1..=8 {
+            clock.press(now * SECOND).unwrap();
+        }
+        assert_eq!(
+            clock.remaining(Color::WHITE, 8 * SECOND),
+            3 * MINUTE - 4 * SECOND
+        );
+
+        // the press that starts the clock is a move too
+        let control = TimeControl::with_stages(vec![
+            Stage {
+                moves: Some(2),
+                time: 1000,
+                timing: Timing::None,
+            },
+            Stage {
+                moves: None,
+                time: 5000,
+                timing: Timing::None,
+            },
+        ])
+        .unwrap();
+        let mut clock = GameClock::new(control, Color::WHITE);
+
+        for now in [0, 10, 20, 30] {
+            clock.press(now).unwrap();
+        }
+        assert_eq!(clock.remaining(Color::WHITE, 30), 5990);
+        assert_eq!(clock.remaining(Color::BLACK, 30), 5980);
+
+        let stage = |moves| Stage {
+            moves,
+            time: MINUTE,
+            timing: Timing::None,
+        };
+        assert!(TimeControl::with_stages(vec![]).is_err());
+        assert!(TimeControl::with_stages(vec![stage(None), stage(None)]).is_err());
+        assert!(TimeControl::with_stages(vec![stage(Some(0))]).is_err());
+    }
+
+    #[test]
+    fn flag() {
+        let mut clock = GameClock::new(
+            TimeControl::new(MINUTE, Timing::Delay(5 * SECOND)),
+            Color::WHITE,
+        );
+
+        // nothing runs before the first press
+        assert_eq!(clock.flagged(10 * MINUTE), None);
+        clock.press(0).unwrap();
+        assert_eq!(clock.turn(), Color::BLACK);
+        assert_eq!(clock.remaining(Color::WHITE, 0), MINUTE);
+
+        assert_eq!(clock.flagged(MINUTE), None);
+        assert_eq!(clock.flagged(MINUTE + 5 * SECOND), Some(Color::BLACK));
+        assert!(matches!(
+            clock.press(2 * MINUTE),
+            Err(Error::FlagFell(Color::BLACK))
+        ));
+        assert!(!clock.is_running());
+        assert_eq!(clock.remaining(Color::BLACK, 2 * MINUTE), 0);
+
+        // a fallen flag stays down
+        clock.start(3 * MINUTE);
+        assert!(!clock.is_running());
+        assert_eq!(clock.flagged(3 * MINUTE), Some(Color::BLACK));
+    }
+}
@@ -1,18 +1,24 @@
 pub const COLOR_MASK: u8 = 128; // 1000 0000
 
-pub const BLACK_PAWN_DELTAS: &'static [i8; 4] = &[-16, -32, -17, -15];
-pub const WHITE_PAWN_DELTAS: &'static [i8; 4] = &[16, 32, 17, 15];
-pub const BISHOP_DELTAS: &'static [i8; 4] = &[17, 15, -17, -15];
-pub const ROOK_DELTAS: &'static [i8; 4] = &[16, -16, 1, -1];
-pub const QUEEN_DELTAS: &'static [i8; 8] = &[16, -16, 1, -1, 17, 15, -17, -15];
-pub const KNIGHT_DELTAS: &'static [i8; 8] = &[14, 31, 18, 33, -14, -31, -18, -33];
-pub const KING_DELTAS: &'static [i8; 10] = &[1, 16, 17, 15, -1, -16, -17, -15, 2, -2];
+pub const DEFAULT_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[cfg(not(feature = "bitboard"))]
+pub const BLACK_PAWN_DELTAS: &[i8; 4] = &[-16, -32, -17, -15];
+#[cfg(not(feature = "bitboard"))]
+pub const WHITE_PAWN_DELTAS: &[i8; 4] = &[16, 32, 17, 15];
+#[cfg(not(feature = "bitboard"))]
+pub const BISHOP_DELTAS: &[i8; 4] = &[17, 15, -17, -15];
+#[cfg(not(feature = "bitboard"))]
+pub const ROOK_DELTAS: &[i8; 4] = &[16, -16, 1, -1];
+pub const QUEEN_DELTAS: &[i8; 8] = &[16, -16, 1, -1, 17, 15, -17, -15];
+pub const KNIGHT_DELTAS: &[i8; 8] = &[14, 31, 18, 33, -14, -31, -18, -33];
+pub const KING_DELTAS: &[i8; 10] = &[1, 16, 17, 15, -1, -16, -17, -15, 2, -2];
 
 // visit these links for explanations
 // https://github.com/jhlywa/chess.js/issues/230
 // https://mediocrechess.blogspot.com/2006/12/guide-attacked-squares.html
 #[rustfmt::skip]
-pub const ATTACKS: &'static [u8; 239] = &[
+pub const ATTACKS: &[u8; 239] = &[
   20, 0, 0, 0, 0, 0, 0, 24,  0, 0, 0, 0, 0, 0,20, 0,
    0,20, 0, 0, 0, 0, 0, 24,  0, 0, 0, 0, 0,20, 0, 0,
    0, 0,20, 0, 0, 0, 0, 24,  0, 0, 0, 0,20, 0, 0, 0,
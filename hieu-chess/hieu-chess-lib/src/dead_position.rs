@@ -0,0 +1,162 @@
+use crate::board::SIZE_0X88;
+use crate::chess::{Color, GameState};
+use crate::constants::KING_DELTAS;
+use crate::piece::PType;
+use crate::square::Square;
+use crate::utils;
+
+fn pawn_push(color: Color) -> i8 {
+    match color {
+        Color::WHITE => 16,
+        Color::BLACK => -16,
+    }
+}
+
+fn pawn_captures(color: Color) -> [i8; 2] {
+    match color {
+        Color::WHITE => [15, 17],
+        Color::BLACK => [-15, -17],
+    }
+}
+
+fn is_pawn(state: &GameState, sq: Square, color: Option<Color>) -> bool {
+    state
+        .board
+        .get(&sq)
+        .is_some_and(|p| p.p_type == PType::PAWN && color.is_none_or(|c| p.color == c))
+}
+
+fn attacked_by_pawn(state: &GameState, sq: Square, by: Color) -> bool {
+    pawn_captures(by)
+        .iter()
+        .filter_map(|delta| sq.add(-delta).ok())
+        .any(|from| is_pawn(state, from, Some(by)))
+}
+
+// walks the king over every square it could ever step on while the pawns stay put, and looks
+// for an enemy pawn it could take. the other king is walked through, since it can move away
+fn king_can_break_through(state: &GameState, king_sq: Square, color: Color) -> bool {
+    let mut visited = [false; SIZE_0X88];
+    let mut stack = vec![king_sq];
+    visited[king_sq.0 as usize] = true;
+
+    while let Some(sq) = stack.pop() {
+        for delta in &KING_DELTAS[..8] {
+            let Ok(to) = sq.add(*delta) else {
+                continue;
+            };
+
+            if visited[to.0 as usize] {
+                continue;
+            }
+            visited[to.0 as usize] = true;
+
+            if attacked_by_pawn(state, to, color.opponent()) {
+                continue;
+            }
+
+            match state.board.get(&to) {
+                Some(p) if p.p_type == PType::PAWN && p.color != color => return true,
+                Some(p) if p.p_type == PType::PAWN => {}
+                _ => stack.push(to),
+            }
+        }
+    }
+
+    false
+}
+
+// only kings and pawns, every pawn blocked by another one and with nothing to capture, and
+// neither king able to reach a pawn it could take. the pawns then can never move again, and
+// kings alone can't give check, so no sequence of moves leads to mate
+pub(crate) fn is_locked(state: &GameState) -> bool {
+    if state.en_passant_sq.is_some() || state.is_in_check {
+        return false;
+    }
+
+    let mut pawns = vec![];
+
+    for idx in 0..SIZE_0X88 {
+        let idx = idx as u8;
+
+        if utils::is_valid_idx(idx).is_err() {
+            continue;
+        }
+
+        match state.board.get(&idx) {
+            None => {}
+            Some(p) if p.p_type == PType::KING => {}
+            Some(p) if p.p_type == PType::PAWN => pawns.push((Square(idx), p.color)),
+            Some(_) => return false,
+        }
+    }
+
+    // without pawns it comes down to insufficient material
+    if pawns.is_empty() {
+        return false;
+    }
+
+    for (sq, color) in &pawns {
+        let blocked = sq
+            .add(pawn_push(*color))
+            .is_ok_and(|ahead| is_pawn(state, ahead, None));
+        let can_capture = pawn_captures(*color)
+            .iter()
+            .filter_map(|delta| sq.add(*delta).ok())
+            .any(|to| is_pawn(state, to, Some(color.opponent())));
+
+        if !blocked || can_capture {
+            return false;
+        }
+    }
+
+    let kings = [
+        (state.white_king_square, Color::WHITE),
+        (state.black_king_square, Color::BLACK),
+    ];
+
+    kings.iter().all(|(king_sq, color)| {
+        king_sq.is_some_and(|king_sq| !king_can_break_through(state, king_sq, *color))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locked_pawns() {
+        let locked = [
+            "8/8/8/1k6/p1p1p1p1/P1P1P1P1/8/3K4 w - - 0 1",
+            "8/8/2k5/8/1p1p1p1p/pPpPpPpP/P1P1P1P1/3K4 b - - 0 1",
+            "4k3/8/8/p1p1p1p1/P1P1P1P1/8/8/4K3 w - - 0 1",
+        ];
+
+        for fen in locked {
+            let mut state = GameState::new();
+            state.load_fen(fen).unwrap();
+
+            assert!(is_locked(&state), "{}", fen);
+        }
+
+        let open = [
+            // the h-file is open, so the white king walks round the chain
+            "8/8/8/1k6/p1p1p3/P1P1P3/8/3K4 w - - 0 1",
+            // the b-pawn isn't blocked
+            "8/8/8/1k6/p1p1p1p1/PPP1P1P1/8/3K4 w - - 0 1",
+            // the f-pawn can take on e4 or g4
+            "8/8/8/1k6/p1p1p1p1/P1P1PP2/8/3K4 w - - 0 1",
+            // a bishop is left on the board
+            "8/8/8/1k6/p1p1p1p1/P1P1P1P1/8/3K1B2 w - - 0 1",
+            // no pawns at all
+            "8/8/8/1k6/8/8/8/3K4 w - - 0 1",
+        ];
+
+        for fen in open {
+            let mut state = GameState::new();
+            state.load_fen(fen).unwrap();
+
+            assert!(!is_locked(&state), "{}", fen);
+        }
+    }
+}
@@ -1,5 +1,47 @@
+use crate::chess::Color;
+use crate::square::Square;
+use std::fmt;
 use thiserror::Error;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FenField {
+    Placement,
+    Turn,
+    Castling,
+    EnPassant,
+    HalfMoves,
+    FullMoves,
+}
+
+impl fmt::Display for FenField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use FenField::*;
+
+        let field = match self {
+            Placement => "piece placement",
+            Turn => "side to move",
+            Castling => "castling rights",
+            EnPassant => "en passant square",
+            HalfMoves => "half move clock",
+            FullMoves => "full move number",
+        };
+
+        write!(f, "{}", field)
+    }
+}
+
+// reasons a set-up position can't have come from a legal game
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    MissingKing(Color),
+    TooManyKings(Color),
+    PawnOnBackRank(Square),
+    OpponentInCheck,
+    CastlingWithoutKing(Color),
+    CastlingWithoutRook(Square),
+    InvalidEnPassant(Square),
+}
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("the move is illegal")]
@@ -22,4 +64,47 @@ pub enum Error {
 
     #[error("invalid color")]
     InvalidColor,
+
+    #[error("invalid SAN move string")]
+    InvalidSan,
+
+    #[error("the SAN move is ambiguous")]
+    AmbiguousSan,
+
+    #[error("invalid UCI move string")]
+    InvalidUci,
+
+    #[error("the move can't be packed into 16 bits")]
+    InvalidPackedMove,
+
+    #[error("the game is already over")]
+    GameOver,
+
+    #[error("there is no draw to claim")]
+    NoDrawToClaim,
+
+    #[error("the game has no ply {0}")]
+    InvalidPly(usize),
+
+    #[error("no such node in the game tree")]
+    UnknownNode,
+
+    #[error("invalid time control")]
+    InvalidTimeControl,
+
+    #[error("the flag has fallen")]
+    FlagFell(Color),
+
+    #[error("invalid FEN {field}: {reason}")]
+    InvalidFen { field: FenField, reason: String },
+
+    #[error("illegal position: {0:?}")]
+    IllegalPosition(Vec<Violation>),
+
+    #[error("invalid PGN at line {line}, column {column}: {reason}")]
+    InvalidPgn {
+        line: usize,
+        column: usize,
+        reason: String,
+    },
 }
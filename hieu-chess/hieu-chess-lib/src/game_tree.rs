@@ -0,0 +1,548 @@
+use crate::chess::{Chess, Color, Move};
+use crate::error::Error;
+use crate::outcome::Outcome;
+use crate::pgn::{self, PgnGame, PgnMove};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct NodeId(usize);
+
+// a move in the tree. the first child is the main continuation, the rest are side lines
+#[derive(Clone, Debug)]
+pub struct Node {
+    m: Option<Move>,
+    san: String,
+    // the side that played the move and the number it was played on, for writing it out
+    color: Color,
+    move_number: u16,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    pub nags: Vec<u8>,
+    pub comments: Vec<String>,
+}
+
+impl Node {
+    // None for the root, which stands for the starting position
+    pub fn m(&self) -> Option<&Move> {
+        self.m.as_ref()
+    }
+
+    pub fn san(&self) -> &str {
+        &self.san
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+}
+
+// a game with all of its variations. the board always shows the position at the current node
+pub struct GameTree {
+    chess: Chess,
+    initial_fen: String,
+    // deleted nodes leave an empty slot, so ids stay valid
+    nodes: Vec<Option<Node>>,
+    current: NodeId,
+}
+
+impl Default for GameTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameTree {
+    pub fn new() -> Self {
+        Self::from_chess(Chess::new())
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Self, Error> {
+        let mut chess = Chess::new();
+        chess.load_fen(fen)?;
+
+        Ok(Self::from_chess(chess))
+    }
+
+    // the whole game with its variations, ending up at the end of the mainline
+    pub fn from_pgn(game: &PgnGame) -> Result<Self, Error> {
+        let mut tree = Self::from_chess(game.start()?);
+        let root = tree.root();
+
+        tree.nodes[0]
+            .as_mut()
+            .expect("the root is never deleted")
+            .comments = game.comments.clone();
+        tree.import_line(root, &game.moves)?;
+
+        let end = tree.mainline().last().copied().unwrap_or(root);
+        tree.go_to(end)?;
+
+        Ok(tree)
+    }
+
+    fn from_chess(chess: Chess) -> Self {
+        let root = Node {
+            m: None,
+            san: String::new(),
+            color: chess.get_turn(),
+            move_number: chess.state().full_move_number(),
+            parent: None,
+            children: vec![],
+            nags: vec![],
+            comments: vec![],
+        };
+
+        Self {
+            initial_fen: chess.get_fen(),
+            chess,
+            nodes: vec![Some(root)],
+            current: NodeId(0),
+        }
+    }
+
+    fn import_line(&mut self, from: NodeId, moves: &[PgnMove]) -> Result<(), Error> {
+        let mut parent = from;
+
+        for pgn_move in moves {
+            self.go_to(parent)?;
+
+            let m = self
+                .chess
+                .parse_san(&pgn_move.san)
+                .map_err(|e| pgn_move.error(e))?;
+            let id = self.add_move(m).map_err(|e| pgn_move.error(e))?;
+
+            let node = self.node_mut(id).expect("the move was just added");
+            node.nags = pgn_move.nags.clone();
+            node.comments = pgn_move.comments.clone();
+
+            for variation in &pgn_move.variations {
+                self.import_line(parent, variation)?;
+            }
+
+            parent = id;
+        }
+
+        Ok(())
+    }
+
+    pub fn chess(&self) -> &Chess {
+        &self.chess
+    }
+
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        self.chess.set_header(name, value);
+    }
+
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    pub fn current(&self) -> NodeId {
+        self.current
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(id.0).and_then(|node| node.as_ref())
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        self.nodes.get_mut(id.0).and_then(|node| node.as_mut())
+    }
+
+    fn get(&self, id: NodeId) -> Result<&Node, Error> {
+        self.node(id).ok_or(Error::UnknownNode)
+    }
+
+    fn get_mut(&mut self, id: NodeId) -> Result<&mut Node, Error> {
+        self.node_mut(id).ok_or(Error::UnknownNode)
+    }
+
+    // the main continuation from the root, root left out
+    pub fn mainline(&self) -> Vec<NodeId> {
+        let mut line = vec![];
+        let mut id = self.root();
+
+        while let Some(&next) = self.get(id).ok().and_then(|node| node.children.first()) {
+            line.push(next);
+            id = next;
+        }
+
+        line
+    }
+
+    // the nodes from the root down to id, root left out
+    fn path(&self, id: NodeId) -> Result<Vec<NodeId>, Error> {
+        let mut path = vec![];
+        let mut node = self.get(id)?;
+        let mut id = id;
+
+        while let Some(parent) = node.parent {
+            path.push(id);
+            id = parent;
+            node = self.get(parent)?;
+        }
+
+        path.reverse();
+
+        Ok(path)
+    }
+
+    // plays m from the current node and moves there. a move that is already in the tree isn't
+    // added twice
+    pub fn add_move(&mut self, m: Move) -> Result<NodeId, Error> {
+        let existing = self
+            .get(self.current)?
+            .children
+            .iter()
+            .copied()
+            .find(|&child| self.node(child).is_some_and(|node| node.m() == Some(&m)));
+
+        if let Some(child) = existing {
+            self.go_to(child)?;
+            return Ok(child);
+        }
+
+        let san = self.chess.move_to_san(&m)?;
+        let color = self.chess.get_turn();
+        let move_number = self.chess.state().full_move_number();
+
+        self.chess.play_move(m.clone())?;
+
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(Some(Node {
+            m: Some(m),
+            san,
+            color,
+            move_number,
+            parent: Some(self.current),
+            children: vec![],
+            nags: vec![],
+            comments: vec![],
+        }));
+
+        let current = self.current;
+        self.get_mut(current)?.children.push(id);
+        self.current = id;
+
+        Ok(id)
+    }
+
+    pub fn add_san(&mut self, san: &str) -> Result<NodeId, Error> {
+        let m = self.chess.parse_san(san)?;
+
+        self.add_move(m)
+    }
+
+    // takes back moves up to the last node both lines share, then plays down to id
+    pub fn go_to(&mut self, id: NodeId) -> Result<(), Error> {
+        let target = self.path(id)?;
+        let current = self.path(self.current)?;
+        let common = target
+            .iter()
+            .zip(&current)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        for _ in common..current.len() {
+            self.chess.undo_move();
+        }
+
+        for node in &target[common..] {
+            let m = self
+                .get(*node)?
+                .m
+                .clone()
+                .expect("only the root has no move");
+            self.chess
+                .play_move(m)
+                .expect("moves in the tree must be legal");
+        }
+
+        self.current = id;
+
+        Ok(())
+    }
+
+    pub fn go_back(&mut self) -> Option<NodeId> {
+        let parent = self.get(self.current).ok()?.parent?;
+        self.go_to(parent).ok()?;
+
+        Some(parent)
+    }
+
+    // follows the main continuation
+    pub fn go_forward(&mut self) -> Option<NodeId> {
+        let next = *self.get(self.current).ok()?.children.first()?;
+        self.go_to(next).ok()?;
+
+        Some(next)
+    }
+
+    // moves the line one place up among its siblings
+    pub fn promote(&mut self, id: NodeId) -> Result<(), Error> {
+        let Some(parent) = self.get(id)?.parent else {
+            return Ok(());
+        };
+        let siblings = &mut self.get_mut(parent)?.children;
+        let idx = siblings
+            .iter()
+            .position(|&sibling| sibling == id)
+            .expect("a node must be among its parent's children");
+
+        if idx > 0 {
+            siblings.swap(idx, idx - 1);
+        }
+
+        Ok(())
+    }
+
+    // makes every move on the way to id the main continuation, so id ends up on the mainline
+    pub fn promote_to_mainline(&mut self, id: NodeId) -> Result<(), Error> {
+        for node in self.path(id)? {
+            let parent = self
+                .get(node)?
+                .parent
+                .expect("the path leaves out the root");
+            let siblings = &mut self.get_mut(parent)?.children;
+
+            siblings.retain(|&sibling| sibling != node);
+            siblings.insert(0, node);
+        }
+
+        Ok(())
+    }
+
+    // removes id and everything after it. deleting the root clears every move
+    pub fn delete(&mut self, id: NodeId) -> Result<(), Error> {
+        let Some(parent) = self.get(id)?.parent else {
+            for child in self.get(id)?.children.clone() {
+                self.delete(child)?;
+            }
+
+            return Ok(());
+        };
+
+        if self.path(self.current)?.contains(&id) {
+            self.go_to(parent)?;
+        }
+
+        self.get_mut(parent)?.children.retain(|&child| child != id);
+
+        let mut stack = vec![id];
+        while let Some(node) = stack.pop() {
+            if let Some(node) = self.nodes[node.0].take() {
+                stack.extend(node.children);
+            }
+        }
+
+        Ok(())
+    }
+
+    // every variation, comment and NAG is written out
+    pub fn to_pgn(&self) -> String {
+        let result = pgn::result(self.mainline_outcome(), self.chess.headers());
+        let mut pgn = pgn::write_headers(&self.initial_fen, self.chess.headers(), result);
+
+        let root = self.get(self.root()).expect("the root is never deleted");
+        let mut tokens: Vec<String> = root
+            .comments
+            .iter()
+            .map(|comment| format!("{{{}}}", comment))
+            .collect();
+
+        self.write_line(self.root(), &mut tokens, true);
+        tokens.push(result.to_string());
+
+        pgn.push_str(&pgn::wrap(&tokens));
+        pgn.push('\n');
+
+        pgn
+    }
+
+    // how the game stands at the end of the mainline
+    fn mainline_outcome(&self) -> Option<Outcome> {
+        let mut chess = Chess::new();
+        chess
+            .load_fen(&self.initial_fen)
+            .expect("the initial position was loaded before");
+
+        for id in self.mainline() {
+            let m = self.get(id).ok().and_then(Node::m).cloned();
+            chess
+                .play_move(m.expect("only the root has no move"))
+                .expect("moves in the tree are legal");
+        }
+
+        chess.outcome()
+    }
+
+    fn write_line(&self, from: NodeId, tokens: &mut Vec<String>, mut force_number: bool) {
+        let mut parent = from;
+
+        while let Some(&main) = self.node(parent).and_then(|node| node.children.first()) {
+            force_number = self.write_move(main, tokens, force_number);
+
+            for &variation in &self.node(parent).expect("parent exists").children[1..] {
+                let mut line = vec![];
+                let after_comment = self.write_move(variation, &mut line, true);
+                self.write_line(variation, &mut line, after_comment);
+
+                line[0].insert(0, '(');
+                line.last_mut().expect("a line has a move").push(')');
+                tokens.append(&mut line);
+
+                // the mainline picks up again after the variation
+                force_number = true;
+            }
+
+            parent = main;
+        }
+    }
+
+    // whether the next move needs its number written out again
+    fn write_move(&self, id: NodeId, tokens: &mut Vec<String>, force_number: bool) -> bool {
+        let node = self.node(id).expect("written nodes exist");
+
+        if node.color == Color::WHITE {
+            tokens.push(format!("{}.", node.move_number));
+        } else if force_number {
+            tokens.push(format!("{}...", node.move_number));
+        }
+
+        tokens.push(node.san.clone());
+        tokens.extend(node.nags.iter().map(|nag| format!("${}", nag)));
+        tokens.extend(
+            node.comments
+                .iter()
+                .map(|comment| format!("{{{}}}", comment)),
+        );
+
+        !node.comments.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::DEFAULT_FEN;
+    use crate::pgn::PgnReader;
+
+    fn sans(tree: &GameTree, ids: &[NodeId]) -> Vec<String> {
+        ids.iter()
+            .map(|&id| tree.node(id).unwrap().san().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn variations() {
+        let mut tree = GameTree::new();
+        for san in ["e4", "e5", "Nf3"] {
+            tree.add_san(san).unwrap();
+        }
+        let nf3 = tree.current();
+
+        let e5 = tree.go_back().unwrap();
+        let bc4 = tree.add_san("Bc4").unwrap();
+        tree.add_san("Nf6").unwrap();
+        assert_eq!(
+            tree.chess().get_fen(),
+            "rnbqkb1r/pppp1ppp/5n2/4p3/2B1P3/8/PPPP1PPP/RNBQK1NR w KQkq - 2 3"
+        );
+
+        // playing a move that is already there just goes to it
+        tree.go_to(e5).unwrap();
+        assert_eq!(tree.add_san("Nf3").unwrap(), nf3);
+        assert_eq!(tree.node(e5).unwrap().children(), [nf3, bc4]);
+
+        assert_eq!(sans(&tree, &tree.mainline()), ["e4", "e5", "Nf3"]);
+        tree.promote_to_mainline(tree.node(bc4).unwrap().children()[0])
+            .unwrap();
+        assert_eq!(sans(&tree, &tree.mainline()), ["e4", "e5", "Bc4", "Nf6"]);
+        tree.promote(nf3).unwrap();
+        assert_eq!(sans(&tree, &tree.mainline()), ["e4", "e5", "Nf3"]);
+
+        // deleting the line the board is on takes the board back to the parent
+        tree.go_to(tree.node(bc4).unwrap().children()[0]).unwrap();
+        tree.delete(bc4).unwrap();
+        assert_eq!(tree.current(), e5);
+        assert!(tree.node(bc4).is_none());
+        assert_eq!(tree.node(e5).unwrap().children(), [nf3]);
+        assert!(matches!(tree.go_to(bc4), Err(Error::UnknownNode)));
+
+        assert_eq!(tree.go_forward(), Some(nf3));
+        assert_eq!(tree.go_forward(), None);
+
+        tree.delete(tree.root()).unwrap();
+        assert_eq!(tree.current(), tree.root());
+        assert!(tree.mainline().is_empty());
+        assert_eq!(tree.chess().get_fen(), DEFAULT_FEN);
+    }
+
+    #[test]
+    fn pgn_round_trip() {
+        let pgn = r#"[Event "Analysis"]
+[Result "*"]
+
+{Leading comment} 1. e4 e5 2. Nf3 Nc6 3. Bb5 {Ruy Lopez} 3... a6 4. Ba4 $5 (4. Bxc6
+dxc6 (4... bxc6 $2) 5. O-O) 4... Nf6 *
+"#;
+        let game = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+        let tree = GameTree::from_pgn(&game).unwrap();
+
+        assert_eq!(tree.current(), *tree.mainline().last().unwrap());
+        assert!(tree
+            .chess()
+            .get_fen()
+            .starts_with("r1bqkb1r/1ppp1ppp/p1n2n2/4p3/B3P3/"));
+
+        let exported = tree.to_pgn();
+        assert!(exported.replace('\n', " ").contains(
+            "3. Bb5 {Ruy Lopez} 3... a6 4. Ba4 $5 (4. Bxc6 dxc6 (4... bxc6 $2) 5. O-O) 4... Nf6 *"
+        ));
+
+        let reimported = PgnReader::new(exported.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(reimported.moves, {
+            let mut moves = game.moves.clone();
+            // only the positions in the source text differ
+            fn strip(moves: &mut [PgnMove], other: &[PgnMove]) {
+                for (m, o) in moves.iter_mut().zip(other) {
+                    m.line = o.line;
+                    m.column = o.column;
+                    for (v, ov) in m.variations.iter_mut().zip(&o.variations) {
+                        strip(v, ov);
+                    }
+                }
+            }
+            strip(&mut moves, &reimported.moves);
+            moves
+        });
+        assert_eq!(reimported.comments, game.comments);
+
+        let tree = GameTree::from_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 12").unwrap();
+        assert!(tree
+            .to_pgn()
+            .contains("[FEN \"r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 12\"]"));
+    }
+
+    #[test]
+    fn pgn_result() {
+        let mut tree = GameTree::new();
+        tree.set_header("Result", "1/2-1/2");
+        tree.add_san("f3").unwrap();
+        assert!(tree.to_pgn().ends_with("1. f3 1/2-1/2\n"));
+
+        for san in ["e5", "g4", "Qh4#"] {
+            tree.add_san(san).unwrap();
+        }
+
+        // the end of the mainline decides, wherever the board is
+        tree.go_to(tree.root()).unwrap();
+        let exported = tree.to_pgn();
+        assert!(exported.contains("[Result \"0-1\"]"));
+        assert!(exported.ends_with("1. f3 e5 2. g4 Qh4# 0-1\n"));
+    }
+}
@@ -1,13 +1,33 @@
+#[cfg(feature = "bitboard")]
+mod bitboard;
 mod board;
 mod chess;
+mod clock;
 mod constants;
+mod dead_position;
 mod error;
+mod game_tree;
 mod move_gen;
+mod move_list;
+mod outcome;
+mod packed_move;
+mod perft;
+mod pgn;
 mod piece;
+mod san;
 mod square;
 mod utils;
+mod zobrist;
 
-pub use chess::{Capture, Chess, Color, Move};
-pub use error::Error;
+pub use chess::{Capture, Chess, Color, Move, Undo};
+pub use clock::{GameClock, Stage, TimeControl, Timing};
+pub use error::{Error, FenField, Violation};
+pub use game_tree::{GameTree, Node, NodeId};
+pub use move_gen::GenMode;
+pub use move_list::{LegalMoves, MoveList, MAX_MOVES};
+pub use outcome::{Outcome, Termination};
+pub use packed_move::PackedMove;
+pub use perft::PerftConfig;
+pub use pgn::{PgnGame, PgnMove, PgnReader};
 pub use piece::{PType, Piece};
 pub use square::{File, Rank, Square};
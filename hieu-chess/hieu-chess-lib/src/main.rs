@@ -0,0 +1,86 @@
+use hieu_chess::{Chess, Move, PerftConfig};
+use std::env;
+use std::process;
+use std::time::Instant;
+
+const USAGE: &str =
+    "usage: hieu-chess perft [--threads <n>] [--hash <entries>] <depth> <fen> [moves...]";
+
+fn exit_with(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+// prints the divide in the format perftree and stockfish's `go perft` use: one `<move> <count>`
+// line per move, an empty line and the total. timing goes to stderr so it doesn't get in the way
+fn perft(mut args: &[String]) {
+    let mut config = PerftConfig::default();
+
+    while let [flag, value, rest @ ..] = args {
+        let field = match flag.as_str() {
+            "--threads" => &mut config.threads,
+            "--hash" => &mut config.hash_entries,
+            _ => break,
+        };
+
+        *field = value
+            .parse()
+            .unwrap_or_else(|_| exit_with(&format!("invalid {}: {}", flag, value)));
+        args = rest;
+    }
+
+    let [depth, fen, moves @ ..] = args else {
+        exit_with(USAGE);
+    };
+
+    let depth: u8 = depth
+        .parse()
+        .unwrap_or_else(|_| exit_with(&format!("invalid depth: {}", depth)));
+
+    let mut chess = Chess::new();
+    if let Err(e) = chess.load_fen(fen) {
+        exit_with(&e.to_string());
+    }
+
+    // perftree passes the moves as a single space separated argument
+    for uci in moves.iter().flat_map(|m| m.split_whitespace()) {
+        let result = Move::from_uci(uci).and_then(|m| chess.play_move(m));
+
+        if let Err(e) = result {
+            exit_with(&format!("{}: {}", uci, e));
+        }
+    }
+
+    let start = Instant::now();
+    let divide = chess.divide_with(depth, config);
+    let elapsed = start.elapsed();
+
+    let total: usize = if depth == 0 {
+        1
+    } else {
+        divide.iter().map(|(_, count)| count).sum()
+    };
+
+    for (m, count) in divide {
+        println!("{} {}", m.to_uci(), count);
+    }
+
+    println!();
+    println!("{}", total);
+
+    eprintln!(
+        "{} nodes in {:.3}s ({:.0} nodes/sec)",
+        total,
+        elapsed.as_secs_f64(),
+        total as f64 / elapsed.as_secs_f64()
+    );
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        Some("perft") => perft(&args[1..]),
+        _ => exit_with(USAGE),
+    }
+}
@@ -1,76 +1,553 @@
+#[cfg(feature = "bitboard")]
+use crate::bitboard;
+use crate::board::Board;
+#[cfg(not(feature = "bitboard"))]
 use crate::board::SIZE_0X88;
 use crate::chess::{Color, GameState, Move};
-use crate::constants::{
-    BISHOP_DELTAS, BLACK_PAWN_DELTAS, KING_DELTAS, KNIGHT_DELTAS, QUEEN_DELTAS, ROOK_DELTAS,
-    WHITE_PAWN_DELTAS,
-};
+#[cfg(not(feature = "bitboard"))]
+use crate::constants::{BISHOP_DELTAS, BLACK_PAWN_DELTAS, ROOK_DELTAS, WHITE_PAWN_DELTAS};
+use crate::constants::{KING_DELTAS, KNIGHT_DELTAS, QUEEN_DELTAS};
+use crate::move_list::MoveList;
 use crate::piece::{PType, Piece, PROMOTION_PIECES};
 use crate::square::Square;
 use crate::utils;
 
 pub struct MoveGen;
 
+// which part of the legal moves to generate
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GenMode {
+    All,
+    // captures (en passant included) and promotions, for quiescence search
+    Captures,
+    // everything that isn't a capture or a promotion
+    Quiets,
+    // moves that put the opponent in check
+    Checks,
+    // every legal move while in check, nothing otherwise
+    Evasions,
+}
+
+// squares are tracked as bits of a u128 by their 0x88 index, so this works with either board
+type SquareMask = u128;
+
+fn mask(sq: Square) -> SquareMask {
+    1 << sq.0
+}
+
+// whether a queen, rook or bishop can attack along a delta
+fn slides_along(p_type: PType, delta: i8) -> bool {
+    let is_diagonal = matches!(delta, 15 | 17 | -15 | -17);
+
+    match p_type {
+        PType::QUEEN => true,
+        PType::ROOK => !is_diagonal,
+        PType::BISHOP => is_diagonal,
+        _ => false,
+    }
+}
+
+// whether a pawn of `color` standing `delta` away from a square attacks it
+fn pawn_attacks_from(color: Color, delta: i8) -> bool {
+    match color {
+        Color::WHITE => delta == -15 || delta == -17,
+        Color::BLACK => delta == 15 || delta == 17,
+    }
+}
+
+// https://www.chessprogramming.org/Checks_and_Pinned_Pieces_(Bitboards)
+// everything the king's square says about the side to move: how many pieces give check, which
+// squares a move has to land on to block or capture the checker, and which pieces are pinned
+// to the king. a pinned piece may still move along the ray (one per direction) that pins it
+struct KingSafety {
+    checkers: u8,
+    check_mask: SquareMask,
+    pinned: SquareMask,
+    pin_rays: [SquareMask; 8],
+}
+
+impl KingSafety {
+    fn new(state: &GameState) -> Self {
+        let mut safety = Self {
+            checkers: 0,
+            check_mask: SquareMask::MAX,
+            pinned: 0,
+            pin_rays: [0; 8],
+        };
+
+        let Some(king_sq) = state.get_current_king_sq() else {
+            return safety;
+        };
+
+        let board = &state.board;
+        let enemy = state.side_to_move.opponent();
+        let mut check_mask = 0;
+
+        for (i, delta) in QUEEN_DELTAS.iter().enumerate() {
+            let mut ray = 0;
+            let mut pinned = None;
+            let mut temp = king_sq;
+
+            while let Ok(to_sq) = temp.add(*delta) {
+                temp = to_sq;
+                ray |= mask(to_sq);
+
+                let Some(piece) = board.get(&to_sq) else {
+                    continue;
+                };
+
+                if piece.color != enemy {
+                    // nothing behind a second friendly piece can pin either of them
+                    if pinned.is_some() {
+                        break;
+                    }
+
+                    pinned = Some(to_sq);
+                    continue;
+                }
+
+                if slides_along(piece.p_type, *delta) {
+                    match pinned {
+                        Some(pinned_sq) => {
+                            safety.pinned |= mask(pinned_sq);
+                            safety.pin_rays[i] = ray;
+                        }
+                        None => {
+                            safety.checkers += 1;
+                            check_mask |= ray;
+                        }
+                    }
+                }
+
+                break;
+            }
+        }
+
+        // knights and pawns can't be blocked, so the only way out is capturing them
+        for delta in KNIGHT_DELTAS.iter().chain(QUEEN_DELTAS) {
+            let Ok(to_sq) = king_sq.add(*delta) else {
+                continue;
+            };
+            let Some(piece) = board.get(&to_sq) else {
+                continue;
+            };
+
+            let is_knight_delta = KNIGHT_DELTAS.contains(delta);
+            let gives_check = piece.color == enemy
+                && if is_knight_delta {
+                    piece.p_type == PType::KNIGHT
+                } else {
+                    piece.p_type == PType::PAWN && pawn_attacks_from(enemy, *delta)
+                };
+
+            if gives_check {
+                safety.checkers += 1;
+                check_mask |= mask(to_sq);
+            }
+        }
+
+        if safety.checkers > 0 {
+            safety.check_mask = check_mask;
+        }
+
+        safety
+    }
+
+    // whether a move of anything but the king leaves the king safe
+    fn allows(&self, m: &Move) -> bool {
+        if self.checkers > 1 || self.check_mask & mask(m.to) == 0 {
+            return false;
+        }
+
+        if self.pinned & mask(m.from) == 0 {
+            return true;
+        }
+
+        self.pin_rays
+            .iter()
+            .any(|ray| ray & mask(m.from) != 0 && ray & mask(m.to) != 0)
+    }
+}
+
 impl MoveGen {
-    // TODO: movegen should not modify game state
-    pub fn moves_for_square(state: &mut GameState, sq: Square) -> Vec<Move> {
+    pub fn moves_for_square(state: &GameState, sq: Square) -> Vec<Move> {
+        let mut moves = MoveList::new();
+        Self::legal_moves_for_square(state, sq, &mut moves);
+
+        moves.to_vec()
+    }
+
+    pub fn legal_moves_for_square(state: &GameState, sq: Square, moves: &mut MoveList) {
         let board = &state.board;
 
         let Some(piece) = board.get(&sq) else {
-            return vec![];
+            return;
         };
 
+        let mut pseudo_legal_moves = MoveList::new();
+
         use PType::*;
-        let pseudo_legal_moves: Vec<Move> = match piece.p_type {
-            QUEEN | ROOK | BISHOP => Self::get_sliding_moves(state, sq, piece),
-            KNIGHT => Self::get_knight_moves(state, sq),
-            PAWN => Self::get_pawn_moves(state, sq, piece),
-            KING => Self::get_king_moves(state, sq),
+        match piece.p_type {
+            QUEEN | ROOK | BISHOP => {
+                Self::get_sliding_moves(state, sq, piece, &mut pseudo_legal_moves)
+            }
+            KNIGHT => Self::get_knight_moves(state, sq, &mut pseudo_legal_moves),
+            PAWN => Self::get_pawn_moves(state, sq, piece, &mut pseudo_legal_moves),
+            KING => Self::get_king_moves(state, sq, &mut pseudo_legal_moves),
         };
 
-        pseudo_legal_moves
-            .into_iter()
-            .filter(|m| {
-                state.make_move(m.clone());
+        if piece.p_type == KING {
+            Self::push_legal_king_moves(state, sq, &pseudo_legal_moves, moves);
+        } else {
+            Self::push_legal_moves(state, &KingSafety::new(state), &pseudo_legal_moves, moves);
+        }
+    }
 
-                let is_legal = if let Some(king) = state.get_current_king_sq() {
-                    !state.is_attacked(king)
-                } else {
-                    true
+    fn push_legal_king_moves(
+        state: &GameState,
+        sq: Square,
+        pseudo_legal_moves: &MoveList,
+        moves: &mut MoveList,
+    ) {
+        // the king is taken off the board, otherwise it would hide the squares behind it from sliders
+        let mut board = state.board.clone();
+        board.remove(&sq);
+
+        for m in pseudo_legal_moves {
+            if !Self::is_attacked(&board, m.to, state.side_to_move.opponent()) {
+                moves.push(m.clone());
+            }
+        }
+    }
+
+    fn push_legal_moves(
+        state: &GameState,
+        safety: &KingSafety,
+        pseudo_legal_moves: &MoveList,
+        moves: &mut MoveList,
+    ) {
+        for m in pseudo_legal_moves {
+            let is_legal = if state.is_enpassant_capture(m) {
+                Self::is_legal_en_passant(state, m)
+            } else {
+                safety.allows(m)
+            };
+
+            if is_legal {
+                moves.push(m.clone());
+            }
+        }
+    }
+
+    // en passant takes two pawns off the same rank at once, which pins don't account for,
+    // so the capture is tried out on a copy of the board instead
+    fn is_legal_en_passant(state: &GameState, m: &Move) -> bool {
+        let Some(king_sq) = state.get_current_king_sq() else {
+            return true;
+        };
+
+        let mut board = state.board.clone();
+        let Some(pawn) = board.get(&m.from).cloned() else {
+            return false;
+        };
+        let delta: i8 = match pawn.color {
+            Color::WHITE => -16,
+            Color::BLACK => 16,
+        };
+        let captured_sq = m.to.add(delta).expect("en passant square must be valid");
+
+        board.remove(&m.from);
+        board.remove(&captured_sq);
+        board.set(pawn, &m.to);
+
+        !Self::is_attacked(&board, king_sq, state.side_to_move.opponent())
+    }
+
+    // whether any piece of `by` attacks the square, whatever stands on it
+    #[cfg(not(feature = "bitboard"))]
+    pub fn is_attacked(board: &Board, sq: Square, by: Color) -> bool {
+        for delta in QUEEN_DELTAS {
+            let mut temp = sq;
+
+            while let Ok(to_sq) = temp.add(*delta) {
+                let Some(piece) = board.get(&to_sq) else {
+                    temp = to_sq;
+                    continue;
                 };
 
-                state.undo();
+                let is_adjacent = temp == sq;
+                let attacks = slides_along(piece.p_type, *delta)
+                    || (is_adjacent && piece.p_type == PType::KING)
+                    || (is_adjacent
+                        && piece.p_type == PType::PAWN
+                        && pawn_attacks_from(by, *delta));
+
+                if piece.color == by && attacks {
+                    return true;
+                }
+
+                break;
+            }
+        }
 
-                is_legal
-            })
-            .collect()
+        KNIGHT_DELTAS.iter().any(|delta| {
+            sq.add(*delta)
+                .is_ok_and(|to_sq| board.get(&to_sq) == Some(&Piece::new(PType::KNIGHT, by)))
+        })
     }
 
-    pub fn moves(state: &mut GameState, color: Color) -> Vec<Move> {
-        let mut moves = vec![];
+    // looks up the attack tables from the square and checks if a piece of the matching type stands on them
+    #[cfg(feature = "bitboard")]
+    pub fn is_attacked(board: &Board, sq: Square, by: Color) -> bool {
+        use PType::*;
+
+        let pieces = |p_type| board.pieces(p_type, by);
+        let idx = bitboard::index(sq);
+        let occupied = board.occupied();
 
-        for idx in 0..SIZE_0X88 {
-            if utils::is_valid_idx(idx as u8).is_err() {
-                continue;
+        bitboard::knight_attacks(idx) & pieces(KNIGHT) != 0
+            || bitboard::king_attacks(idx) & pieces(KING) != 0
+            || bitboard::pawn_attacks(by.opponent(), idx) & pieces(PAWN) != 0
+            || bitboard::rook_attacks(idx, occupied) & (pieces(ROOK) | pieces(QUEEN)) != 0
+            || bitboard::bishop_attacks(idx, occupied) & (pieces(BISHOP) | pieces(QUEEN)) != 0
+    }
+
+    pub fn moves(state: &GameState, color: Color) -> Vec<Move> {
+        let mut moves = MoveList::new();
+        Self::legal_moves(state, color, &mut moves);
+
+        moves.to_vec()
+    }
+
+    // the legal moves of the side to move that match the mode
+    pub fn generate(state: &GameState, mode: GenMode, moves: &mut MoveList) {
+        let us = state.side_to_move;
+
+        if mode == GenMode::Evasions {
+            let in_check = state
+                .get_current_king_sq()
+                .is_some_and(|king_sq| state.is_attacked(king_sq));
+
+            if !in_check {
+                return;
             }
+        }
+
+        let keep: fn(&GameState, &Move) -> bool = match mode {
+            GenMode::Captures => {
+                Self::tactical_moves(state, moves);
+                return;
+            }
+            GenMode::All | GenMode::Evasions => |_, _| true,
+            GenMode::Quiets => |state, m| m.promotion_piece.is_none() && !state.is_capture(m),
+            GenMode::Checks => Self::gives_check,
+        };
+
+        let mut legal_moves = MoveList::new();
+        Self::legal_moves(state, us, &mut legal_moves);
+
+        for m in &legal_moves {
+            if keep(state, m) {
+                moves.push(m.clone());
+            }
+        }
+    }
+
+    // plays the move out on a copy of the board and looks at the opponent's king
+    fn gives_check(state: &GameState, m: &Move) -> bool {
+        let us = state.side_to_move;
+        let enemy_king = match us {
+            Color::WHITE => state.black_king_square,
+            Color::BLACK => state.white_king_square,
+        };
+        let Some(enemy_king) = enemy_king else {
+            return false;
+        };
 
-            let Some(piece) = state.board.get(&(idx as u8)) else {
+        let mut board = state.board.clone();
+        let Some(piece) = board.get(&m.from).cloned() else {
+            return false;
+        };
+
+        if state.is_enpassant_capture(m) {
+            let delta: i8 = match us {
+                Color::WHITE => -16,
+                Color::BLACK => 16,
+            };
+            board.remove(&m.to.add(delta).expect("en passant square must be valid"));
+        }
+
+        if piece.p_type == PType::KING {
+            let rook_squares = if state.is_castling_kingside(m) {
+                Some((1, -1))
+            } else if state.is_castling_queenside(m) {
+                Some((-2, 1))
+            } else {
+                None
+            };
+
+            if let Some((from, to)) = rook_squares {
+                board.remove(&m.to.add(from).expect("castling square must be valid"));
+                board.set(
+                    Piece::new(PType::ROOK, us),
+                    &m.to.add(to).expect("castling square must be valid"),
+                );
+            }
+        }
+
+        board.remove(&m.from);
+        board.set(m.promotion_piece.clone().unwrap_or(piece), &m.to);
+
+        Self::is_attacked(&board, enemy_king, us)
+    }
+
+    // captures and promotions only. the pieces look for enemy pieces to take instead of
+    // generating every move, and nothing but the king moves in double check
+    fn tactical_moves(state: &GameState, moves: &mut MoveList) {
+        let safety = KingSafety::new(state);
+
+        for sq in Self::squares_of(state, state.side_to_move) {
+            let Some(piece) = state.board.get(&sq) else {
                 continue;
             };
 
-            if piece.color == color {
-                moves.append(&mut Self::moves_for_square(
-                    state,
-                    (idx as u8).try_into().unwrap(),
-                ));
+            let is_king = piece.p_type == PType::KING;
+            if !is_king && safety.checkers > 1 {
+                continue;
+            }
+
+            let mut pseudo_legal_moves = MoveList::new();
+            Self::get_tactical_moves(state, sq, piece, safety.check_mask, &mut pseudo_legal_moves);
+
+            if is_king {
+                Self::push_legal_king_moves(state, sq, &pseudo_legal_moves, moves);
+            } else {
+                Self::push_legal_moves(state, &safety, &pseudo_legal_moves, moves);
             }
         }
+    }
+
+    // pawns have few enough moves to filter, everything else only looks at enemy pieces. apart
+    // from the king, a capture has to land on the check mask to be legal
+    fn get_tactical_moves(
+        state: &GameState,
+        from_sq: Square,
+        piece: &Piece,
+        check_mask: SquareMask,
+        moves: &mut MoveList,
+    ) {
+        if piece.p_type == PType::PAWN {
+            let mut pawn_moves = MoveList::new();
+            Self::get_pawn_moves(state, from_sq, piece, &mut pawn_moves);
+
+            for m in &pawn_moves {
+                if m.promotion_piece.is_some() || m.from.file() != m.to.file() {
+                    moves.push(m.clone());
+                }
+            }
+
+            return;
+        }
+
+        let check_mask = if piece.p_type == PType::KING {
+            SquareMask::MAX
+        } else {
+            check_mask
+        };
+
+        let mut targets = Self::attacked_enemies(state, from_sq, piece) & check_mask;
+
+        while targets != 0 {
+            let to_sq = Square(targets.trailing_zeros() as u8);
+            targets &= targets - 1;
+
+            moves.push(Move::new(from_sq, to_sq, None));
+        }
+    }
 
-        moves
+    #[cfg(not(feature = "bitboard"))]
+    fn squares_of(state: &GameState, color: Color) -> impl Iterator<Item = Square> + '_ {
+        (0..SIZE_0X88 as u8)
+            .filter(|idx| utils::is_valid_idx(*idx).is_ok())
+            .filter(move |idx| state.board.get(idx).is_some_and(|p| p.color == color))
+            .map(Square)
     }
 
-    fn get_pawn_moves(state: &GameState, from_sq: Square, piece: &Piece) -> Vec<Move> {
+    #[cfg(feature = "bitboard")]
+    fn squares_of(state: &GameState, color: Color) -> impl Iterator<Item = Square> + '_ {
+        bitboard::squares(state.board.color(color))
+    }
+
+    // the enemy pieces a knight, king or slider could take
+    #[cfg(not(feature = "bitboard"))]
+    fn attacked_enemies(state: &GameState, from_sq: Square, piece: &Piece) -> SquareMask {
+        use PType::*;
+        let (deltas, slides): (&[i8], bool) = match piece.p_type {
+            QUEEN => (QUEEN_DELTAS, true),
+            ROOK => (ROOK_DELTAS, true),
+            BISHOP => (BISHOP_DELTAS, true),
+            KNIGHT => (KNIGHT_DELTAS, false),
+            // castling never captures
+            KING => (&KING_DELTAS[..8], false),
+            PAWN => (&[], false),
+        };
+
+        let mut targets = 0;
+
+        for delta in deltas {
+            let mut temp = from_sq;
+
+            while let Ok(to_sq) = temp.add(*delta) {
+                if let Some(p) = state.board.get(&to_sq) {
+                    if p.color != state.side_to_move {
+                        targets |= mask(to_sq);
+                    }
+
+                    break;
+                }
+
+                if !slides {
+                    break;
+                }
+
+                temp = to_sq;
+            }
+        }
+
+        targets
+    }
+
+    #[cfg(feature = "bitboard")]
+    fn attacked_enemies(state: &GameState, from_sq: Square, piece: &Piece) -> SquareMask {
+        let board = &state.board;
+        let idx = bitboard::index(from_sq);
+        let occupied = board.occupied();
+
+        use PType::*;
+        let attacks = match piece.p_type {
+            QUEEN => {
+                bitboard::rook_attacks(idx, occupied) | bitboard::bishop_attacks(idx, occupied)
+            }
+            ROOK => bitboard::rook_attacks(idx, occupied),
+            BISHOP => bitboard::bishop_attacks(idx, occupied),
+            KNIGHT => bitboard::knight_attacks(idx),
+            KING => bitboard::king_attacks(idx),
+            PAWN => 0,
+        };
+
+        bitboard::squares(attacks & board.color(state.side_to_move.opponent()))
+            .fold(0, |targets, sq| targets | mask(sq))
+    }
+
+    pub fn legal_moves(state: &GameState, color: Color, moves: &mut MoveList) {
+        for sq in Self::squares_of(state, color) {
+            Self::legal_moves_for_square(state, sq, moves);
+        }
+    }
+
+    #[cfg(not(feature = "bitboard"))]
+    fn get_pawn_moves(state: &GameState, from_sq: Square, piece: &Piece, moves: &mut MoveList) {
         let board = &state.board;
-        let mut moves: Vec<Move> = vec![];
 
         let deltas = match piece.color {
             Color::BLACK => BLACK_PAWN_DELTAS,
@@ -137,14 +614,11 @@ impl MoveGen {
                 }
             }
         }
-
-        moves
     }
 
-    fn get_king_moves(state: &GameState, from_sq: Square) -> Vec<Move> {
+    fn get_king_moves(state: &GameState, from_sq: Square, moves: &mut MoveList) {
         let board = &state.board;
 
-        let mut moves: Vec<Move> = vec![];
         let deltas = KING_DELTAS;
 
         for delta in deltas {
@@ -178,7 +652,7 @@ impl MoveGen {
                         }
                     } else if state.is_castling_queenside(&m) {
                         for i in 1..4 {
-                            let to = from_sq.add(-1 * i).expect("castling range must be valid");
+                            let to = from_sq.add(-i).expect("castling range must be valid");
                             if state.is_occupied(&to) {
                                 can_castle = false;
                                 break;
@@ -186,7 +660,7 @@ impl MoveGen {
                         }
 
                         for i in 1..3 {
-                            let to = from_sq.add(-1 * i).expect("castling range must be valid");
+                            let to = from_sq.add(-i).expect("castling range must be valid");
                             if state.is_attacked(to) {
                                 can_castle = false;
                                 break;
@@ -204,14 +678,12 @@ impl MoveGen {
                 moves.push(m);
             }
         }
-
-        moves
     }
 
-    fn get_knight_moves(state: &GameState, from_sq: Square) -> Vec<Move> {
+    #[cfg(not(feature = "bitboard"))]
+    fn get_knight_moves(state: &GameState, from_sq: Square, moves: &mut MoveList) {
         let board = &state.board;
 
-        let mut moves: Vec<Move> = vec![];
         let deltas = KNIGHT_DELTAS;
 
         for delta in deltas {
@@ -226,14 +698,12 @@ impl MoveGen {
                 moves.push(Move::new(from_sq, to_sq, None));
             }
         }
-
-        moves
     }
 
-    fn get_sliding_moves(state: &GameState, from_sq: Square, piece: &Piece) -> Vec<Move> {
+    #[cfg(not(feature = "bitboard"))]
+    fn get_sliding_moves(state: &GameState, from_sq: Square, piece: &Piece, moves: &mut MoveList) {
         let board = &state.board;
 
-        let mut moves: Vec<Move> = vec![];
         let mut temp;
 
         use PType::*;
@@ -262,14 +732,89 @@ impl MoveGen {
                 temp = to_sq;
             }
         }
+    }
+
+    #[cfg(feature = "bitboard")]
+    fn get_pawn_moves(state: &GameState, from_sq: Square, piece: &Piece, moves: &mut MoveList) {
+        let board = &state.board;
+        let idx = bitboard::index(from_sq);
+        let empty = !board.occupied();
+
+        // pawns move a rank (8 bits) up for white and down for black, off-board shifts just drop out
+        let forward = |bb: u64| match piece.color {
+            Color::WHITE => bb << 8,
+            Color::BLACK => bb >> 8,
+        };
+
+        let mut targets = forward(1 << idx) & empty;
+
+        // pawn can only move forward two squares if it hasn't already moved
+        if (from_sq.rank() == 1 && piece.color == Color::WHITE)
+            || (from_sq.rank() == 6 && piece.color == Color::BLACK)
+        {
+            targets |= forward(targets) & empty;
+        }
+
+        let mut enemies = board.occupied() & !board.color(state.side_to_move);
+        if let Some(sq) = state.en_passant_sq {
+            enemies |= 1 << bitboard::index(sq);
+        }
+        targets |= bitboard::pawn_attacks(piece.color, idx) & enemies;
+
+        for to_sq in bitboard::squares(targets) {
+            // a promotion move if the pawn reaches the last rank, otherwise it's a regular move
+            if to_sq.rank() == 0 || to_sq.rank() == 7 {
+                for mut p in PROMOTION_PIECES {
+                    p.color = piece.color;
 
-        moves
+                    moves.push(Move::new(from_sq, to_sq, Some(p)));
+                }
+            } else {
+                moves.push(Move::new(from_sq, to_sq, None));
+            }
+        }
+    }
+
+    #[cfg(feature = "bitboard")]
+    fn get_knight_moves(state: &GameState, from_sq: Square, moves: &mut MoveList) {
+        let targets = bitboard::knight_attacks(bitboard::index(from_sq));
+
+        Self::bitboard_moves(state, from_sq, targets, moves);
+    }
+
+    #[cfg(feature = "bitboard")]
+    fn get_sliding_moves(state: &GameState, from_sq: Square, piece: &Piece, moves: &mut MoveList) {
+        let idx = bitboard::index(from_sq);
+        let occupied = state.board.occupied();
+
+        use PType::*;
+        let targets = match piece.p_type {
+            QUEEN => {
+                bitboard::rook_attacks(idx, occupied) | bitboard::bishop_attacks(idx, occupied)
+            }
+            ROOK => bitboard::rook_attacks(idx, occupied),
+            BISHOP => bitboard::bishop_attacks(idx, occupied),
+            _ => 0,
+        };
+
+        Self::bitboard_moves(state, from_sq, targets, moves);
+    }
+
+    // every target square that isn't taken by a friendly piece is a move
+    #[cfg(feature = "bitboard")]
+    fn bitboard_moves(state: &GameState, from_sq: Square, targets: u64, moves: &mut MoveList) {
+        let friendly = state.board.color(state.side_to_move);
+
+        for to_sq in bitboard::squares(targets & !friendly) {
+            moves.push(Move::new(from_sq, to_sq, None));
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
+        constants::DEFAULT_FEN,
         piece::{PType::*, Piece},
         square::{File, Rank},
     };
@@ -285,7 +830,7 @@ mod tests {
 
         state.board.set(p, &sq);
 
-        let mut moves: Vec<String> = MoveGen::moves_for_square(&mut state, sq)
+        let mut moves: Vec<String> = MoveGen::moves_for_square(&state, sq)
             .iter()
             .map(|m| m.to.get_notation())
             .collect();
@@ -319,7 +864,7 @@ mod tests {
             .board
             .set(enemy_blocker, &Square::new(Rank::Four, File::C));
 
-        let mut moves: Vec<String> = MoveGen::moves_for_square(&mut state, sq)
+        let mut moves: Vec<String> = MoveGen::moves_for_square(&state, sq)
             .iter()
             .map(|m| m.to.get_notation())
             .collect();
@@ -346,7 +891,7 @@ mod tests {
 
         state.board.set(pawn, &sq);
 
-        let mut moves: Vec<String> = MoveGen::moves_for_square(&mut state, sq)
+        let mut moves: Vec<String> = MoveGen::moves_for_square(&state, sq)
             .iter()
             .map(|m| m.to.get_notation())
             .collect();
@@ -362,7 +907,7 @@ mod tests {
         state
             .board
             .set(blocking_piece, &Square::new(Rank::Four, File::E));
-        let mut moves: Vec<String> = MoveGen::moves_for_square(&mut state, sq)
+        let mut moves: Vec<String> = MoveGen::moves_for_square(&state, sq)
             .iter()
             .map(|m| m.to.get_notation())
             .collect();
@@ -385,7 +930,7 @@ mod tests {
 
         state.board.set(pawn, &sq);
 
-        let mut moves: Vec<String> = MoveGen::moves_for_square(&mut state, sq)
+        let mut moves: Vec<String> = MoveGen::moves_for_square(&state, sq)
             .iter()
             .map(|m| m.to.get_notation())
             .collect();
@@ -400,7 +945,7 @@ mod tests {
         state
             .board
             .set(blocking_piece, &Square::new(Rank::Six, File::D));
-        let mut moves: Vec<String> = MoveGen::moves_for_square(&mut state, sq)
+        let mut moves: Vec<String> = MoveGen::moves_for_square(&state, sq)
             .iter()
             .map(|m| m.to.get_notation())
             .collect();
@@ -424,12 +969,12 @@ mod tests {
         state.board.set(white_pawn, &white_sq);
         state.board.set(black_pawn, &black_sq);
 
-        let mut moves_for_white: Vec<String> = MoveGen::moves_for_square(&mut state, white_sq)
+        let mut moves_for_white: Vec<String> = MoveGen::moves_for_square(&state, white_sq)
             .iter()
             .map(|m| m.to.get_notation())
             .collect();
         state.side_to_move = Color::BLACK;
-        let mut moves_for_black: Vec<String> = MoveGen::moves_for_square(&mut state, black_sq)
+        let mut moves_for_black: Vec<String> = MoveGen::moves_for_square(&state, black_sq)
             .iter()
             .map(|m| m.to.get_notation())
             .collect();
@@ -457,18 +1002,18 @@ mod tests {
         state.board.set(white_pawn, &white_sq);
         state.board.set(black_pawn, &black_sq);
 
-        let mut moves_for_white: Vec<String> = MoveGen::moves_for_square(&mut state, white_sq)
+        let moves_for_white: Vec<String> = MoveGen::moves_for_square(&state, white_sq)
             .iter()
             .map(|m| m.to.get_notation())
             .collect();
         state.side_to_move = Color::BLACK;
-        let mut moves_for_black: Vec<String> = MoveGen::moves_for_square(&mut state, black_sq)
+        let moves_for_black: Vec<String> = MoveGen::moves_for_square(&state, black_sq)
             .iter()
             .map(|m| m.to.get_notation())
             .collect();
 
-        let mut expected_moves_for_white = ["e8"; 4];
-        let mut expected_moves_for_black = ["f1"; 4];
+        let expected_moves_for_white = ["e8"; 4];
+        let expected_moves_for_black = ["f1"; 4];
 
         assert_eq!(moves_for_white, expected_moves_for_white);
         assert_eq!(moves_for_black, expected_moves_for_black);
@@ -477,11 +1022,13 @@ mod tests {
     #[test]
     fn moves_in_check() {
         let mut state = GameState::new();
-        state.load_fen("8/2bN4/8/2K3qk/8/8/3Q4/8 w - - 0 1");
+        state
+            .load_fen("8/2bN4/8/2K3qk/8/8/3Q4/8 w - - 0 1")
+            .unwrap();
         // state.load_fen("1bn5/3N4/p7/2K3qk/4P3/8/3Q4/8 w - - 0 1");
         let sq = Square::new(Rank::Two, File::D);
 
-        let moves: Vec<String> = MoveGen::moves_for_square(&mut state, sq)
+        let moves: Vec<String> = MoveGen::moves_for_square(&state, sq)
             .iter()
             .map(|m| m.to.get_notation())
             .collect();
@@ -490,13 +1037,177 @@ mod tests {
         assert!(state.captures.is_empty());
 
         // let sq = Square::new(Rank::Four, File::E);
-        // let moves: Vec<String> = MoveGen::moves_for_square(&mut state, sq)
+        // let moves: Vec<String> = MoveGen::moves_for_square(&state, sq)
         //     .iter()
         //     .map(|m| m.to.get_notation())
         //     .collect();
         // println!("{:?}", moves);
     }
 
+    #[test]
+    fn pinned_pieces() {
+        let mut state = GameState::new();
+        state
+            .load_fen("4k3/4r3/8/1b6/8/3B4/4R3/4K2q w - - 0 1")
+            .unwrap();
+        let state = state;
+
+        let moves_for = |sq: &str| -> Vec<String> {
+            let mut moves: Vec<String> = MoveGen::moves_for_square(&state, sq.try_into().unwrap())
+                .iter()
+                .map(|m| m.to.get_notation())
+                .collect();
+            moves.sort();
+            moves
+        };
+
+        // the bishop is not pinned, but the king is in check from h1
+        assert!(moves_for("d3").is_empty());
+        // the rook is pinned on the e-file, and can't block the check along the first rank either
+        assert!(moves_for("e2").is_empty());
+        assert_eq!(moves_for("e1"), ["d2", "f2"]);
+
+        let mut state = GameState::new();
+        state.load_fen("4k3/4r3/8/8/8/8/4R3/4K3 w - - 0 1").unwrap();
+        let mut moves: Vec<String> = MoveGen::moves_for_square(&state, "e2".try_into().unwrap())
+            .iter()
+            .map(|m| m.to.get_notation())
+            .collect();
+        moves.sort();
+
+        assert_eq!(moves, ["e3", "e4", "e5", "e6", "e7"]);
+    }
+
+    #[test]
+    fn en_passant_discovered_check() {
+        let mut state = GameState::new();
+        state.load_fen("8/8/8/K2pP2r/8/8/8/7k w - d6 0 1").unwrap();
+
+        let moves: Vec<String> = MoveGen::moves_for_square(&state, "e5".try_into().unwrap())
+            .iter()
+            .map(|m| m.to.get_notation())
+            .collect();
+
+        // taking en passant would leave the king on the same rank as the rook
+        assert_eq!(moves, ["e6"]);
+    }
+
+    #[test]
+    fn generate_modes() {
+        let generate = |fen: &str, mode| {
+            let mut state = GameState::new();
+            state.load_fen(fen).unwrap();
+
+            let mut moves = MoveList::new();
+            MoveGen::generate(&state, mode, &mut moves);
+
+            let mut moves: Vec<String> = moves.iter().map(|m| m.to_uci()).collect();
+            moves.sort();
+            moves
+        };
+
+        // every mode is the full move list filtered down
+        let fens = [
+            DEFAULT_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            "4k3/8/8/8/Rb6/8/8/1N2K3 w - - 0 1",
+            "4k3/8/8/8/1b6/8/4r3/2N1K3 w - - 0 1",
+        ];
+
+        for fen in fens {
+            let mut state = GameState::new();
+            state.load_fen(fen).unwrap();
+
+            let in_check = state
+                .get_current_king_sq()
+                .is_some_and(|king_sq| state.is_attacked(king_sq));
+            let tactical = |m: &Move| m.promotion_piece.is_some() || state.is_capture(m);
+            let predicate = |mode, m: &Move| match mode {
+                GenMode::All => true,
+                GenMode::Captures => tactical(m),
+                GenMode::Quiets => !tactical(m),
+                GenMode::Checks => MoveGen::gives_check(&state, m),
+                GenMode::Evasions => in_check,
+            };
+
+            for mode in [
+                GenMode::All,
+                GenMode::Captures,
+                GenMode::Quiets,
+                GenMode::Checks,
+                GenMode::Evasions,
+            ] {
+                let mut expected: Vec<String> = MoveGen::moves(&state, state.side_to_move)
+                    .iter()
+                    .filter(|m| predicate(mode, m))
+                    .map(|m| m.to_uci())
+                    .collect();
+                expected.sort();
+
+                assert_eq!(generate(fen, mode), expected, "{:?} in {}", mode, fen);
+            }
+
+            // captures and quiets split the moves between them
+            let mut split = generate(fen, GenMode::Captures);
+            split.extend(generate(fen, GenMode::Quiets));
+            split.sort();
+            assert_eq!(split, generate(fen, GenMode::All), "{}", fen);
+        }
+
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        assert_eq!(
+            generate(kiwipete, GenMode::Captures),
+            ["d5e6", "e2a6", "e5d7", "e5f7", "e5g6", "f3f6", "f3h3", "g2h3"]
+        );
+        assert!(generate(kiwipete, GenMode::Evasions).is_empty());
+
+        // en passant counts as a capture
+        assert_eq!(
+            generate(
+                "r3k2r/8/8/8/3pP3/8/8/R3K2R b KQkq e3 0 1",
+                GenMode::Captures
+            ),
+            ["a8a1", "d4e3", "h8h1"]
+        );
+
+        // promotions are tactical with or without a capture
+        let promotion = "1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(
+            generate(promotion, GenMode::Captures),
+            ["a7a8b", "a7a8n", "a7a8q", "a7a8r", "a7b8b", "a7b8n", "a7b8q", "a7b8r"]
+        );
+        assert_eq!(
+            generate(promotion, GenMode::Quiets),
+            ["e1d1", "e1d2", "e1e2", "e1f1", "e1f2"]
+        );
+        assert_eq!(generate(promotion, GenMode::Checks), ["a7b8q", "a7b8r"]);
+
+        // the bishop checks from b4: the king steps aside, the knight blocks or the rook takes
+        let check = "4k3/8/8/8/Rb6/8/8/1N2K3 w - - 0 1";
+        assert_eq!(
+            generate(check, GenMode::Evasions),
+            ["a4b4", "b1c3", "b1d2", "e1d1", "e1e2", "e1f1", "e1f2"]
+        );
+        assert_eq!(generate(check, GenMode::Captures), ["a4b4"]);
+        assert_eq!(
+            generate(check, GenMode::Quiets),
+            ["b1c3", "b1d2", "e1d1", "e1e2", "e1f1", "e1f2"]
+        );
+        assert!(generate(check, GenMode::Checks).is_empty());
+
+        // in double check only the king may take
+        let double_check = "4k3/8/8/8/1b6/8/4r3/2N1K3 w - - 0 1";
+        assert_eq!(generate(double_check, GenMode::Captures), ["e1e2"]);
+        assert_eq!(
+            generate(double_check, GenMode::Evasions),
+            ["e1d1", "e1e2", "e1f1"]
+        );
+    }
+
     struct TestCase {
         fen: String,
         expected: Vec<&'static str>,
@@ -512,7 +1223,7 @@ mod tests {
             }
         }
 
-        fn match_case(&self, result: &mut Vec<String>) -> bool {
+        fn match_case(&self, result: &mut [String]) -> bool {
             result.sort();
 
             let matching = self
@@ -568,7 +1279,7 @@ mod tests {
 
         for test in tests {
             state.reset();
-            state.load_fen(&test.fen);
+            state.load_fen(&test.fen).unwrap();
 
             let sq = if state.side_to_move == Color::WHITE {
                 state.white_king_square.unwrap()
@@ -576,7 +1287,7 @@ mod tests {
                 state.black_king_square.unwrap()
             };
 
-            let mut moves: Vec<String> = MoveGen::moves_for_square(&mut state, sq)
+            let mut moves: Vec<String> = MoveGen::moves_for_square(&state, sq)
                 .iter()
                 .map(|m| m.to.get_notation())
                 .collect();
@@ -612,11 +1323,11 @@ mod tests {
 
         for (i, test) in tests.iter().enumerate() {
             state.reset();
-            state.load_fen(&test.fen);
+            state.load_fen(&test.fen).unwrap();
 
             let sq: Square = squares[i].try_into().unwrap();
 
-            let mut moves: Vec<String> = MoveGen::moves_for_square(&mut state, sq)
+            let mut moves: Vec<String> = MoveGen::moves_for_square(&state, sq)
                 .iter()
                 .map(|m| m.to.get_notation())
                 .collect();
@@ -634,12 +1345,13 @@ mod tests {
     fn moves() {
         let mut state = GameState::new();
         // state.load_fen("8/8/3p4/KPp4r/R4p1k/8/4P1P1/8 w - c6 0 1");
-        state.load_fen("8/8/3p4/KPp4r/R4p1k/8/4P1P1/8 w - c6 0 1");
-        let a: Vec<(String, String)> =
-            MoveGen::moves_for_square(&mut state, "b5".try_into().unwrap())
-                .iter()
-                .map(|m| (m.from.get_notation(), m.to.get_notation()))
-                .collect();
+        state
+            .load_fen("8/8/3p4/KPp4r/R4p1k/8/4P1P1/8 w - c6 0 1")
+            .unwrap();
+        let a: Vec<(String, String)> = MoveGen::moves_for_square(&state, "b5".try_into().unwrap())
+            .iter()
+            .map(|m| (m.from.get_notation(), m.to.get_notation()))
+            .collect();
         println!("{:?}", a);
     }
 }
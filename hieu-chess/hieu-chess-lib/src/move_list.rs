@@ -0,0 +1,152 @@
+use crate::board::SIZE_0X88;
+use crate::chess::{GameState, Move};
+use crate::move_gen::MoveGen;
+use crate::square::Square;
+use crate::utils;
+use std::ops::Deref;
+
+// no legal chess position has more than 218 moves
+pub const MAX_MOVES: usize = 256;
+
+const ARRAY_REPEAT_VALUE: Move = Move {
+    from: Square(0),
+    to: Square(0),
+    promotion_piece: None,
+};
+
+// a fixed capacity list of moves that lives on the stack, so generating moves doesn't allocate
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MoveList {
+    pub fn new() -> Self {
+        Self {
+            moves: [ARRAY_REPEAT_VALUE; MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, m: Move) {
+        self.moves[self.len] = m;
+        self.len += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &Self::Target {
+        &self.moves[..self.len]
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// lazily yields the legal moves of the side to move, one square's worth at a time
+pub struct LegalMoves<'a> {
+    state: &'a GameState,
+    idx: usize,
+    buffer: MoveList,
+    next: usize,
+}
+
+impl<'a> LegalMoves<'a> {
+    pub(crate) fn new(state: &'a GameState) -> Self {
+        Self {
+            state,
+            idx: 0,
+            buffer: MoveList::new(),
+            next: 0,
+        }
+    }
+}
+
+impl Iterator for LegalMoves<'_> {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.next == self.buffer.len() {
+            if self.idx >= SIZE_0X88 {
+                return None;
+            }
+
+            let idx = self.idx as u8;
+            self.idx += 1;
+
+            if utils::is_valid_idx(idx).is_err() {
+                continue;
+            }
+
+            match self.state.board.get(&idx) {
+                Some(piece) if piece.color == self.state.side_to_move => {}
+                _ => continue,
+            }
+
+            self.buffer.clear();
+            self.next = 0;
+            MoveGen::legal_moves_for_square(self.state, Square(idx), &mut self.buffer);
+        }
+
+        let m = self.buffer[self.next].clone();
+        self.next += 1;
+
+        Some(m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn move_list() {
+        let mut moves = MoveList::new();
+        assert!(moves.is_empty());
+
+        moves.push(Move::from_str("e2", "e4", None));
+        moves.push(Move::from_str("g1", "f3", None));
+
+        assert_eq!(moves.len(), 2);
+        assert!(moves.contains(&Move::from_str("g1", "f3", None)));
+        assert_eq!(
+            moves.iter().map(|m| m.to_uci()).collect::<Vec<_>>(),
+            ["e2e4", "g1f3"]
+        );
+
+        moves.clear();
+        assert!(moves.is_empty());
+    }
+
+    #[test]
+    fn legal_moves() {
+        let mut state = GameState::new();
+        state
+            .load_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+
+        let lazy: Vec<Move> = LegalMoves::new(&state).collect();
+
+        assert_eq!(lazy.len(), 48);
+        assert_eq!(lazy, MoveGen::moves(&state, state.side_to_move));
+    }
+}
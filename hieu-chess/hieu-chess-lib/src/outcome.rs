@@ -0,0 +1,76 @@
+use crate::chess::Color;
+use serde::{Deserialize, Serialize};
+
+// how a game ended. the first group is read off the position, the last three can only be set
+// from outside, see Chess::resign, Chess::timeout and Chess::agree_draw
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Termination {
+    Checkmate,
+    Stalemate,
+    InsufficientMaterial,
+    // the pawns are locked and neither king can break through
+    DeadPosition,
+    // a draw either side may claim
+    ThreefoldRepetition,
+    FiftyMoves,
+    // drawn automatically, without a claim
+    FivefoldRepetition,
+    SeventyFiveMoves,
+    Resignation,
+    Timeout,
+    Agreement,
+}
+
+impl From<Termination> for &'static str {
+    fn from(termination: Termination) -> &'static str {
+        match termination {
+            Termination::Checkmate => "checkmate",
+            Termination::Stalemate => "stalemate",
+            Termination::InsufficientMaterial => "insufficient material",
+            Termination::DeadPosition => "dead position",
+            Termination::ThreefoldRepetition => "threefold repetition",
+            Termination::FiftyMoves => "fifty-move rule",
+            Termination::FivefoldRepetition => "fivefold repetition",
+            Termination::SeventyFiveMoves => "seventy-five-move rule",
+            Termination::Resignation => "resignation",
+            Termination::Timeout => "timeout",
+            Termination::Agreement => "agreement",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Outcome {
+    // None for a draw
+    pub winner: Option<Color>,
+    pub termination: Termination,
+}
+
+impl Outcome {
+    pub fn win(winner: Color, termination: Termination) -> Self {
+        Self {
+            winner: Some(winner),
+            termination,
+        }
+    }
+
+    pub fn draw(termination: Termination) -> Self {
+        Self {
+            winner: None,
+            termination,
+        }
+    }
+
+    pub fn is_draw(&self) -> bool {
+        self.winner.is_none()
+    }
+
+    // the PGN result token
+    pub fn result(&self) -> &'static str {
+        match self.winner {
+            Some(Color::WHITE) => "1-0",
+            Some(Color::BLACK) => "0-1",
+            None => "1/2-1/2",
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use crate::chess::{Color, Move};
+use crate::error::Error;
+use crate::piece::{PType, Piece};
+use crate::square::Square;
+
+// a move in two bytes, laid out from the lowest bit:
+//   0-5   from square (rank * 8 + file)
+//   6-11  to square
+//   12-13 promotion piece: knight, bishop, rook, queen
+//   14-15 flag: 0 for a regular move, 1 for a white promotion, 2 for a black promotion
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PackedMove(u16);
+
+const FROM_SHIFT: u16 = 0;
+const TO_SHIFT: u16 = 6;
+const PROMOTION_SHIFT: u16 = 12;
+const FLAG_SHIFT: u16 = 14;
+
+const WHITE_PROMOTION: u16 = 1;
+const BLACK_PROMOTION: u16 = 2;
+
+const PROMOTION_TYPES: [PType; 4] = [PType::KNIGHT, PType::BISHOP, PType::ROOK, PType::QUEEN];
+
+fn pack_square(sq: Square) -> u16 {
+    (sq.rank() * 8 + sq.file()) as u16
+}
+
+fn unpack_square(bits: u16) -> Square {
+    let idx = (bits & 0x3f) as u8;
+
+    Square((idx / 8) * 16 + idx % 8)
+}
+
+impl PackedMove {
+    pub fn bits(self) -> u16 {
+        self.0
+    }
+
+    pub fn from_sq(self) -> Square {
+        unpack_square(self.0 >> FROM_SHIFT)
+    }
+
+    pub fn to_sq(self) -> Square {
+        unpack_square(self.0 >> TO_SHIFT)
+    }
+
+    pub fn promotion_piece(self) -> Option<Piece> {
+        let color = match self.0 >> FLAG_SHIFT {
+            WHITE_PROMOTION => Color::WHITE,
+            BLACK_PROMOTION => Color::BLACK,
+            _ => return None,
+        };
+        let p_type = PROMOTION_TYPES[((self.0 >> PROMOTION_SHIFT) & 3) as usize];
+
+        Some(Piece::new(p_type, color))
+    }
+
+    // big endian, the order the bytes go over the wire
+    pub fn to_bytes(self) -> [u8; 2] {
+        self.0.to_be_bytes()
+    }
+
+    pub fn from_bytes(bytes: [u8; 2]) -> Result<Self, Error> {
+        u16::from_be_bytes(bytes).try_into()
+    }
+}
+
+impl TryFrom<u16> for PackedMove {
+    type Error = Error;
+
+    fn try_from(bits: u16) -> Result<Self, Self::Error> {
+        let flag = bits >> FLAG_SHIFT;
+
+        // promotion bits on a regular move would be lost on the way back
+        if flag > BLACK_PROMOTION || (flag == 0 && (bits >> PROMOTION_SHIFT) & 3 != 0) {
+            return Err(Error::InvalidPackedMove);
+        }
+
+        Ok(Self(bits))
+    }
+}
+
+impl TryFrom<&Move> for PackedMove {
+    type Error = Error;
+
+    fn try_from(m: &Move) -> Result<Self, Self::Error> {
+        let mut bits = pack_square(m.from) << FROM_SHIFT | pack_square(m.to) << TO_SHIFT;
+
+        if let Some(ref piece) = m.promotion_piece {
+            let Some(idx) = PROMOTION_TYPES.iter().position(|t| *t == piece.p_type) else {
+                return Err(Error::InvalidPackedMove);
+            };
+            let flag = match piece.color {
+                Color::WHITE => WHITE_PROMOTION,
+                Color::BLACK => BLACK_PROMOTION,
+            };
+
+            bits |= (idx as u16) << PROMOTION_SHIFT | flag << FLAG_SHIFT;
+        }
+
+        Ok(Self(bits))
+    }
+}
+
+impl From<PackedMove> for Move {
+    fn from(m: PackedMove) -> Self {
+        Move::new(m.from_sq(), m.to_sq(), m.promotion_piece())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::GameState;
+    use crate::move_gen::MoveGen;
+
+    #[test]
+    fn round_trip() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N w - - 0 1",
+        ];
+
+        for fen in fens {
+            let mut state = GameState::new();
+            state.load_fen(fen).unwrap();
+
+            for m in MoveGen::moves(&state, state.side_to_move) {
+                let packed = PackedMove::try_from(&m).unwrap();
+                let bytes = PackedMove::from_bytes(packed.to_bytes()).unwrap();
+
+                assert_eq!(Move::from(packed), m);
+                assert_eq!(bytes, packed);
+            }
+        }
+    }
+
+    #[test]
+    fn layout() {
+        let m = Move::from_str("e7", "e8", Some(Piece::new(PType::QUEEN, Color::WHITE)));
+        let packed = PackedMove::try_from(&m).unwrap();
+
+        assert_eq!(packed.bits(), 52 | 60 << 6 | 3 << 12 | 1 << 14);
+        assert_eq!(packed.to_bytes(), [0x7f, 0x34]);
+
+        let m = Move::from_str("a2", "a1", Some(Piece::new(PType::KNIGHT, Color::BLACK)));
+        assert_eq!(PackedMove::try_from(&m).unwrap().bits(), 8 | 2 << 14);
+    }
+
+    #[test]
+    fn invalid() {
+        let m = Move::from_str("e7", "e8", Some(Piece::new(PType::KING, Color::WHITE)));
+
+        assert!(matches!(
+            PackedMove::try_from(&m),
+            Err(Error::InvalidPackedMove)
+        ));
+        assert!(matches!(
+            PackedMove::try_from(3 << 14),
+            Err(Error::InvalidPackedMove)
+        ));
+        assert!(matches!(
+            PackedMove::try_from(2 << 12),
+            Err(Error::InvalidPackedMove)
+        ));
+        assert!(matches!(
+            PackedMove::from_bytes([0x30, 0x00]),
+            Err(Error::InvalidPackedMove)
+        ));
+        assert!(PackedMove::try_from(1 << 14 | 3 << 12).is_ok());
+    }
+}
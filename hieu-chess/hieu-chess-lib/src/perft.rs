@@ -0,0 +1,182 @@
+use crate::chess::{GameState, Move};
+use crate::move_gen::MoveGen;
+use crate::move_list::MoveList;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+// how a deep perft run is spread out. each thread takes the next root move off a shared counter
+// and counts it on its own copy of the state, with its own table of subtree counts
+#[derive(Clone, Copy, Debug)]
+pub struct PerftConfig {
+    pub threads: usize,
+    // number of entries in each thread's table, 0 turns hashing off
+    pub hash_entries: usize,
+}
+
+impl Default for PerftConfig {
+    fn default() -> Self {
+        Self {
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            hash_entries: 1 << 18,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Entry {
+    key: u64,
+    depth: u8,
+    nodes: usize,
+}
+
+// subtree counts keyed by the zobrist hash, a newer entry always replaces the one in its slot
+struct PerftTable {
+    entries: Vec<Entry>,
+}
+
+impl PerftTable {
+    fn new(size: usize) -> Self {
+        let size = if size == 0 {
+            0
+        } else {
+            size.next_power_of_two()
+        };
+
+        Self {
+            entries: vec![Entry::default(); size],
+        }
+    }
+
+    fn slot(&self, key: u64) -> usize {
+        key as usize & (self.entries.len() - 1)
+    }
+
+    fn get(&self, key: u64, depth: u8) -> Option<usize> {
+        if self.entries.is_empty() {
+            return None;
+        }
+
+        let entry = &self.entries[self.slot(key)];
+        (entry.key == key && entry.depth == depth).then_some(entry.nodes)
+    }
+
+    fn insert(&mut self, key: u64, depth: u8, nodes: usize) {
+        if self.entries.is_empty() {
+            return;
+        }
+
+        let slot = self.slot(key);
+        self.entries[slot] = Entry { key, depth, nodes };
+    }
+}
+
+fn perft_hashed(state: &mut GameState, depth: u8, table: &mut PerftTable) -> usize {
+    if depth == 0 {
+        return 1;
+    }
+
+    let key = state.hash();
+    if let Some(nodes) = table.get(key, depth) {
+        return nodes;
+    }
+
+    let mut moves = MoveList::new();
+    MoveGen::legal_moves(state, state.side_to_move, &mut moves);
+
+    // the moves are all legal, so the last ply doesn't need to be played out
+    if depth == 1 {
+        return moves.len();
+    }
+
+    let mut nodes = 0;
+
+    for m in &moves {
+        let undo = state.make_unchecked(m);
+        nodes += perft_hashed(state, depth - 1, table);
+        state.unmake(m, undo);
+    }
+
+    table.insert(key, depth, nodes);
+
+    nodes
+}
+
+pub(crate) fn divide(state: &GameState, depth: u8, config: PerftConfig) -> Vec<(Move, usize)> {
+    if depth == 0 {
+        return vec![];
+    }
+
+    let root_moves = MoveGen::moves(state, state.side_to_move);
+    let next = AtomicUsize::new(0);
+    let mut counts = vec![0; root_moves.len()];
+
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..config.threads.max(1))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut state = state.clone();
+                    let mut table = PerftTable::new(config.hash_entries);
+                    let mut counted = vec![];
+
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(m) = root_moves.get(i) else {
+                            break;
+                        };
+
+                        let undo = state.make_unchecked(m);
+                        counted.push((i, perft_hashed(&mut state, depth - 1, &mut table)));
+                        state.unmake(m, undo);
+                    }
+
+                    counted
+                })
+            })
+            .collect();
+
+        for worker in workers {
+            for (i, nodes) in worker.join().expect("perft worker panicked") {
+                counts[i] = nodes;
+            }
+        }
+    });
+
+    root_moves.into_iter().zip(counts).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parallel_and_hashed() {
+        let mut state = GameState::new();
+        state
+            .load_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+
+        let configs = [
+            PerftConfig {
+                threads: 1,
+                hash_entries: 0,
+            },
+            PerftConfig {
+                threads: 4,
+                hash_entries: 0,
+            },
+            PerftConfig {
+                threads: 3,
+                hash_entries: 1000,
+            },
+        ];
+
+        for config in configs {
+            let divide = divide(&state, 3, config);
+
+            assert_eq!(divide.len(), 48);
+            assert_eq!(divide.iter().map(|(_, n)| n).sum::<usize>(), 97862);
+        }
+
+        assert!(divide(&state, 0, PerftConfig::default()).is_empty());
+    }
+}
@@ -0,0 +1,868 @@
+use crate::chess::{Chess, Color, GameState};
+use crate::constants::DEFAULT_FEN;
+use crate::error::Error;
+use crate::outcome::Outcome;
+use crate::san;
+use std::io::BufRead;
+
+// https://www.chessclub.com/help/PGN-spec
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+const MAX_LINE_LENGTH: usize = 80;
+
+// game termination markers
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+pub fn write_pgn(state: &GameState, headers: &[(String, String)], result: &str) -> String {
+    let mut replay = state.initial_state();
+    let mut pgn = write_headers(&replay.get_fen(), headers, result);
+
+    let mut tokens: Vec<String> = vec![];
+    let mut move_number = replay.full_move_number() as usize;
+
+    for (i, m) in state.played_moves().into_iter().enumerate() {
+        if replay.side_to_move == Color::WHITE {
+            tokens.push(format!("{}.", move_number));
+        } else if i == 0 {
+            tokens.push(format!("{}...", move_number));
+        }
+
+        tokens.push(san::move_to_san(&replay, &m).expect("moves in history must be legal"));
+
+        if replay.side_to_move == Color::BLACK {
+            move_number += 1;
+        }
+
+        replay.play_move(m).expect("moves in history must be legal");
+    }
+
+    tokens.push(result.to_string());
+
+    pgn.push_str(&wrap(&tokens));
+    pgn.push('\n');
+
+    pgn
+}
+
+// the termination marker for the moves written out: the outcome after the last of them if
+// there is one, otherwise the Result header
+pub(crate) fn result(outcome: Option<Outcome>, headers: &[(String, String)]) -> &str {
+    match outcome {
+        Some(outcome) => outcome.result(),
+        None => headers
+            .iter()
+            .find(|(name, value)| name == "Result" && RESULTS.contains(&value.as_str()))
+            .map_or("*", |(_, value)| value.as_str()),
+    }
+}
+
+// the tag pairs and the blank line that ends them
+pub(crate) fn write_headers(
+    initial_fen: &str,
+    headers: &[(String, String)],
+    result: &str,
+) -> String {
+    let mut pgn = String::new();
+
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    };
+
+    for (name, default) in SEVEN_TAG_ROSTER {
+        let value = match name {
+            "Result" => result,
+            _ => header(name).unwrap_or(default),
+        };
+
+        pgn.push_str(&tag_pair(name, value));
+    }
+
+    if initial_fen != DEFAULT_FEN {
+        pgn.push_str(&tag_pair("SetUp", "1"));
+        pgn.push_str(&tag_pair("FEN", initial_fen));
+    }
+
+    for (name, value) in headers {
+        let is_reserved =
+            SEVEN_TAG_ROSTER.iter().any(|(n, _)| n == name) || name == "SetUp" || name == "FEN";
+
+        if !is_reserved {
+            pgn.push_str(&tag_pair(name, value));
+        }
+    }
+
+    pgn.push('\n');
+
+    pgn
+}
+
+fn tag_pair(name: &str, value: &str) -> String {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+
+    format!("[{} \"{}\"]\n", name, value)
+}
+
+pub(crate) fn wrap(tokens: &[String]) -> String {
+    let mut lines: Vec<String> = vec![];
+    let mut line = String::new();
+
+    for token in tokens {
+        if !line.is_empty() && line.len() + 1 + token.len() > MAX_LINE_LENGTH {
+            lines.push(line);
+            line = String::new();
+        }
+
+        if !line.is_empty() {
+            line.push(' ');
+        }
+
+        line.push_str(token);
+    }
+
+    lines.push(line);
+    lines.join("\n")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnMove {
+    pub san: String,
+    pub nags: Vec<u8>,
+    pub comments: Vec<String>,
+    // alternatives to this move, each one starting from the position before it
+    pub variations: Vec<Vec<PgnMove>>,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PgnGame {
+    pub headers: Vec<(String, String)>,
+    // comments that come before the first move
+    pub comments: Vec<String>,
+    pub moves: Vec<PgnMove>,
+    pub result: Option<String>,
+}
+
+impl PgnGame {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    // plays the mainline from the starting position, or from the FEN tag if there is one
+    pub fn replay(&self) -> Result<Chess, Error> {
+        let mut chess = self.start()?;
+
+        for m in &self.moves {
+            chess.play_san(&m.san).map_err(|e| m.error(e))?;
+        }
+
+        // a result the position can't show was a resignation or an agreed draw
+        if chess.outcome().is_none() {
+            match self.result.as_deref().or(self.header("Result")) {
+                Some("1-0") => chess.resign(Color::BLACK)?,
+                Some("0-1") => chess.resign(Color::WHITE)?,
+                Some("1/2-1/2") if chess.claimable_draw().is_some() => chess.claim_draw()?,
+                Some("1/2-1/2") => chess.agree_draw()?,
+                _ => {}
+            }
+        }
+
+        Ok(chess)
+    }
+
+    // the position before the first move, with the headers set
+    pub(crate) fn start(&self) -> Result<Chess, Error> {
+        let mut chess = Chess::new();
+
+        if let Some(fen) = self.header("FEN") {
+            chess.load_fen(fen).map_err(|e| Error::InvalidPgn {
+                line: 1,
+                column: 1,
+                reason: e.to_string(),
+            })?;
+        }
+
+        for (name, value) in &self.headers {
+            chess.set_header(name, value);
+        }
+
+        Ok(chess)
+    }
+}
+
+impl PgnMove {
+    // points at this move when it can't be played
+    pub(crate) fn error(&self, e: Error) -> Error {
+        Error::InvalidPgn {
+            line: self.line,
+            column: self.column,
+            reason: format!("{}: {}", self.san, e),
+        }
+    }
+}
+
+// reads games one at a time, so large databases never have to be loaded into memory at once
+pub struct PgnReader<R: BufRead> {
+    reader: R,
+    buf: Vec<char>,
+    pos: usize,
+    line: usize,
+    eof: bool,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: vec![],
+            pos: 0,
+            line: 0,
+            eof: false,
+        }
+    }
+
+    pub fn read_game(&mut self) -> Option<Result<PgnGame, Error>> {
+        match self.parse_game() {
+            Ok(Some(game)) => Some(Ok(game)),
+            Ok(None) => None,
+            Err(e) => {
+                self.skip_to_next_game();
+                Some(Err(e))
+            }
+        }
+    }
+
+    fn parse_game(&mut self) -> Result<Option<PgnGame>, Error> {
+        self.skip_whitespace()?;
+
+        if self.peek_char()?.is_none() {
+            return Ok(None);
+        }
+
+        let mut game = PgnGame::default();
+
+        while self.peek_char()? == Some('[') {
+            self.next_char()?;
+            game.headers.push(self.parse_tag()?);
+            self.skip_whitespace()?;
+        }
+
+        let mut result = None;
+        game.moves = self.parse_line(0, &mut game.comments, &mut result)?;
+        game.result = result;
+
+        Ok(Some(game))
+    }
+
+    fn parse_tag(&mut self) -> Result<(String, String), Error> {
+        self.skip_whitespace()?;
+
+        let mut name = String::new();
+        while let Some(c) = self.peek_char()? {
+            if !c.is_ascii_alphanumeric() && c != '_' {
+                break;
+            }
+
+            name.push(c);
+            self.next_char()?;
+        }
+
+        if name.is_empty() {
+            return Err(self.error("expected a tag name"));
+        }
+
+        self.skip_whitespace()?;
+        let (line, column) = (self.line, self.pos + 1);
+        if self.next_char()? != Some('"') {
+            return Err(self.error("expected a tag value"));
+        }
+
+        let mut value = String::new();
+        loop {
+            match self.next_char()? {
+                Some('\\') => match self.next_char()? {
+                    Some(c) => value.push(c),
+                    None => return Err(self.error_at(line, column, "unterminated tag value")),
+                },
+                Some('"') => break,
+                Some('\n') | None => {
+                    return Err(self.error_at(line, column, "unterminated tag value"))
+                }
+                Some(c) => value.push(c),
+            }
+        }
+
+        self.skip_whitespace()?;
+        if self.next_char()? != Some(']') {
+            return Err(self.error("expected ']' after the tag value"));
+        }
+
+        Ok((name, value))
+    }
+
+    // parses moves until the end of the game or, inside a variation, until the closing ')'
+    fn parse_line(
+        &mut self,
+        depth: usize,
+        leading_comments: &mut Vec<String>,
+        result: &mut Option<String>,
+    ) -> Result<Vec<PgnMove>, Error> {
+        let mut moves: Vec<PgnMove> = vec![];
+
+        loop {
+            self.skip_whitespace()?;
+
+            let (line, column) = (self.line, self.pos + 1);
+
+            let Some(c) = self.peek_char()? else {
+                if depth > 0 {
+                    return Err(self.error("unterminated variation"));
+                }
+                break;
+            };
+
+            match c {
+                '{' | ';' => {
+                    self.next_char()?;
+                    let comment = if c == '{' {
+                        self.read_until('}')?
+                    } else {
+                        self.read_until('\n')?
+                    };
+
+                    match moves.last_mut() {
+                        Some(m) => m.comments.push(comment.trim().to_string()),
+                        None => leading_comments.push(comment.trim().to_string()),
+                    }
+                }
+                '$' => {
+                    self.next_char()?;
+                    let digits = self.read_symbol()?;
+                    let Ok(nag) = digits.parse::<u8>() else {
+                        return Err(self.error_at(line, column, "invalid NAG"));
+                    };
+
+                    match moves.last_mut() {
+                        Some(m) => m.nags.push(nag),
+                        None => return Err(self.error_at(line, column, "NAG before any move")),
+                    }
+                }
+                '(' => {
+                    self.next_char()?;
+
+                    if moves.is_empty() {
+                        return Err(self.error_at(line, column, "variation before any move"));
+                    }
+
+                    let mut comments = vec![];
+                    let mut variation = self.parse_line(depth + 1, &mut comments, &mut None)?;
+
+                    if let Some(first) = variation.first_mut() {
+                        comments.append(&mut first.comments);
+                        first.comments = comments;
+                    }
+
+                    moves
+                        .last_mut()
+                        .expect("moves was checked above")
+                        .variations
+                        .push(variation);
+                }
+                ')' => {
+                    if depth == 0 {
+                        return Err(self.error_at(line, column, "unexpected ')'"));
+                    }
+
+                    self.next_char()?;
+                    return Ok(moves);
+                }
+                // a new tag section without a result token for the previous game
+                '[' if depth == 0 => break,
+                '.' => {
+                    self.next_char()?;
+                }
+                '*' => {
+                    self.next_char()?;
+                    self.set_result(depth, line, column, result, "*")?;
+                    break;
+                }
+                c if c.is_ascii_alphanumeric() => {
+                    let symbol = self.read_symbol()?;
+
+                    if RESULTS.contains(&symbol.as_str()) {
+                        self.set_result(depth, line, column, result, &symbol)?;
+                        break;
+                    }
+
+                    // move number indication, the periods are skipped on their own
+                    if symbol.chars().all(|c| c.is_ascii_digit()) {
+                        continue;
+                    }
+
+                    let mut glyph = String::new();
+                    while let Some(c @ ('!' | '?')) = self.peek_char()? {
+                        glyph.push(c);
+                        self.next_char()?;
+                    }
+
+                    let nags = match glyph.as_str() {
+                        "" => vec![],
+                        "!" => vec![1],
+                        "?" => vec![2],
+                        "!!" => vec![3],
+                        "??" => vec![4],
+                        "!?" => vec![5],
+                        "?!" => vec![6],
+                        _ => return Err(self.error("invalid move suffix annotation")),
+                    };
+
+                    moves.push(PgnMove {
+                        san: symbol,
+                        nags,
+                        comments: vec![],
+                        variations: vec![],
+                        line,
+                        column,
+                    });
+                }
+                _ => {
+                    return Err(self.error_at(
+                        line,
+                        column,
+                        &format!("unexpected character '{}'", c),
+                    ))
+                }
+            }
+        }
+
+        Ok(moves)
+    }
+
+    fn set_result(
+        &self,
+        depth: usize,
+        line: usize,
+        column: usize,
+        result: &mut Option<String>,
+        token: &str,
+    ) -> Result<(), Error> {
+        if depth > 0 {
+            return Err(self.error_at(line, column, "game result inside a variation"));
+        }
+
+        *result = Some(token.to_string());
+
+        Ok(())
+    }
+
+    fn read_symbol(&mut self) -> Result<String, Error> {
+        let mut symbol = String::new();
+
+        while let Some(c) = self.peek_char()? {
+            if !c.is_ascii_alphanumeric() && !"_+#=:-/".contains(c) {
+                break;
+            }
+
+            symbol.push(c);
+            self.next_char()?;
+        }
+
+        Ok(symbol)
+    }
+
+    fn read_until(&mut self, end: char) -> Result<String, Error> {
+        let (line, column) = (self.line, self.pos);
+        let mut text = String::new();
+
+        loop {
+            match self.next_char()? {
+                Some(c) if c == end => return Ok(text),
+                Some(c) => text.push(c),
+                // a line comment may run up to the end of the input
+                None if end == '\n' => return Ok(text),
+                None => return Err(self.error_at(line, column, "unterminated comment")),
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), Error> {
+        while let Some(c) = self.peek_char()? {
+            if !c.is_whitespace() {
+                break;
+            }
+
+            self.next_char()?;
+        }
+
+        Ok(())
+    }
+
+    // throws away the rest of a game that failed to parse. it ends at its termination token, or
+    // failing that at the first tag after a blank line following the movetext, so the tags left
+    // after an error in the tag section aren't read as a game of their own
+    fn skip_to_next_game(&mut self) {
+        let mut in_movetext = false;
+        let mut after_blank = false;
+
+        loop {
+            let line: String = self.buf.iter().collect();
+            let line = line.trim();
+
+            if line.is_empty() {
+                after_blank = in_movetext;
+            } else if line.starts_with('[') {
+                if after_blank {
+                    break;
+                }
+            } else {
+                in_movetext = true;
+                after_blank = false;
+
+                if line
+                    .split_whitespace()
+                    .last()
+                    .is_some_and(|token| RESULTS.contains(&token))
+                {
+                    self.pos = self.buf.len();
+                    break;
+                }
+            }
+
+            self.pos = self.buf.len();
+
+            if self.fill().is_err() || self.eof {
+                break;
+            }
+        }
+    }
+
+    fn peek_char(&mut self) -> Result<Option<char>, Error> {
+        self.fill()?;
+
+        Ok(self.buf.get(self.pos).copied())
+    }
+
+    fn next_char(&mut self) -> Result<Option<char>, Error> {
+        let c = self.peek_char()?;
+        self.pos += 1;
+
+        Ok(c)
+    }
+
+    // loads the next line once the current one has been consumed
+    fn fill(&mut self) -> Result<(), Error> {
+        while self.pos >= self.buf.len() && !self.eof {
+            let mut line = String::new();
+
+            match self.reader.read_line(&mut line) {
+                Ok(0) => self.eof = true,
+                Ok(_) => {
+                    self.line += 1;
+                    self.pos = 0;
+
+                    // lines starting with % are escaped and ignored entirely
+                    self.buf = if line.starts_with('%') {
+                        vec!['\n']
+                    } else {
+                        line.chars().collect()
+                    };
+                }
+                Err(e) => return Err(self.error(&e.to_string())),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn error(&self, reason: &str) -> Error {
+        self.error_at(self.line, self.pos + 1, reason)
+    }
+
+    fn error_at(&self, line: usize, column: usize, reason: &str) -> Error {
+        Error::InvalidPgn {
+            line,
+            column,
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_game()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::Move;
+    use crate::outcome::Termination;
+
+    #[test]
+    fn export_finished_game() {
+        let mut chess = Chess::new();
+        chess.set_header("Event", "Casual \"blitz\" game");
+        chess.set_header("White", "Hieu");
+        chess.set_header("TimeControl", "300+2");
+
+        for m in ["e4", "e5", "Bc4", "Nc6", "Qh5", "Nf6", "Qxf7#"] {
+            chess.play_san(m).unwrap();
+        }
+
+        assert_eq!(
+            chess.to_pgn(),
+            [
+                "[Event \"Casual \\\"blitz\\\" game\"]",
+                "[Site \"?\"]",
+                "[Date \"????.??.??\"]",
+                "[Round \"?\"]",
+                "[White \"Hieu\"]",
+                "[Black \"?\"]",
+                "[Result \"1-0\"]",
+                "[TimeControl \"300+2\"]",
+                "",
+                "1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7# 1-0",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn export_from_position() {
+        let mut chess = Chess::new();
+        chess
+            .load_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 12")
+            .unwrap();
+
+        chess.play_move(Move::from_str("e8", "g8", None)).unwrap();
+        chess.play_move(Move::from_str("e1", "c1", None)).unwrap();
+
+        let pgn = chess.to_pgn();
+        assert!(pgn.contains("[Result \"*\"]\n[SetUp \"1\"]\n"));
+        assert!(pgn.contains("[FEN \"r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 12\"]\n"));
+        assert!(pgn.ends_with("\n\n12... O-O 13. O-O-O *\n"));
+    }
+
+    #[test]
+    fn wraps_movetext() {
+        let mut chess = Chess::new();
+
+        // the fifth time the start position comes up the game is drawn
+        for _ in 0..4 {
+            for m in ["Nf3", "Nf6", "Ng1", "Ng8"] {
+                chess.play_san(m).unwrap();
+            }
+        }
+
+        let pgn = chess.to_pgn();
+        let movetext: Vec<&str> = pgn.split("\n\n").nth(1).unwrap().lines().collect();
+
+        assert!(movetext.len() > 1);
+        assert!(movetext.iter().all(|line| line.len() <= 80));
+        assert!(movetext[0].starts_with("1. Nf3 Nf6 2. Ng1 Ng8"));
+        assert!(movetext.last().unwrap().ends_with("1/2-1/2"));
+    }
+
+    const GAMES: &str = r#"[Event "F/S Return Match"]
+[Site "Belgrade, Serbia JUG"]
+[Date "1992.11.04"]
+[Round "29"]
+[White "Fischer, Robert J."]
+[Black "Spassky, Boris V."]
+[Result "1/2-1/2"]
+
+{Leading comment} 1. e4 e5 2. Nf3 Nc6 3. Bb5 {This opening is called the Ruy Lopez.}
+3... a6 4. Ba4!? ( 4. Bxc6 dxc6 ( 4... bxc6 $2 ) 5. O-O ; the exchange variation
+) 4... Nf6 $1 5. O-O Be7 1/2-1/2
+
+% an escaped line that must be ignored
+[Event "Second game"]
+[Result "0-1"]
+
+1. f3 e5 2. g4 Qh4# 0-1
+
+[Event "No result token"]
+
+1. d4 d5
+"#;
+
+    #[test]
+    fn read_games() {
+        let games: Vec<PgnGame> = PgnReader::new(GAMES.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(games.len(), 3);
+
+        let game = &games[0];
+        assert_eq!(game.headers.len(), 7);
+        assert_eq!(game.header("White"), Some("Fischer, Robert J."));
+        assert_eq!(game.result.as_deref(), Some("1/2-1/2"));
+        assert_eq!(game.comments, vec!["Leading comment"]);
+
+        let sans: Vec<&str> = game.moves.iter().map(|m| m.san.as_str()).collect();
+        assert_eq!(
+            sans,
+            ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Ba4", "Nf6", "O-O", "Be7"]
+        );
+
+        assert_eq!(
+            game.moves[4].comments,
+            vec!["This opening is called the Ruy Lopez."]
+        );
+        assert_eq!(game.moves[6].nags, vec![5]);
+        assert_eq!(game.moves[7].nags, vec![1]);
+        assert_eq!((game.moves[6].line, game.moves[6].column), (10, 12));
+
+        let variation = &game.moves[6].variations[0];
+        let sans: Vec<&str> = variation.iter().map(|m| m.san.as_str()).collect();
+        assert_eq!(sans, ["Bxc6", "dxc6", "O-O"]);
+        assert_eq!(variation[2].comments, vec!["the exchange variation"]);
+        assert_eq!(variation[1].variations[0][0].san, "bxc6");
+        assert_eq!(variation[1].variations[0][0].nags, vec![2]);
+
+        let chess = games[0].replay().unwrap();
+        assert!(chess
+            .get_fen()
+            .starts_with("r1bqk2r/1pppbppp/p1n2n2/4p3/B3P3/5N2/PPPP1PPP/RNBQ1RK1 w kq - 4 "));
+        assert_eq!(chess.get_header("Round"), Some("29"));
+
+        assert_eq!(games[1].header("Event"), Some("Second game"));
+        assert!(games[1].replay().unwrap().is_checkmate());
+
+        assert_eq!(games[2].moves.len(), 2);
+        assert_eq!(games[2].result, None);
+    }
+
+    #[test]
+    fn read_errors() {
+        let tests = [
+            ("[Event \"unterminated]\n1. e4 *", 1, 8),
+            ("[Event \"x\"]\n\n1. e4 {no end", 3, 7),
+            ("1. e4 e5 2. Nf3 ) *", 1, 17),
+            ("1. e4 (1. d4 *", 1, 14),
+            ("$1 1. e4 *", 1, 1),
+            ("1. e4 e5 @ *", 1, 10),
+        ];
+
+        for (pgn, line, column) in tests {
+            match PgnReader::new(pgn.as_bytes()).next() {
+                Some(Err(Error::InvalidPgn {
+                    line: l, column: c, ..
+                })) => assert_eq!((l, c), (line, column), "{}", pgn),
+                _ => panic!("expected an error for {}", pgn),
+            }
+        }
+
+        let game = PgnReader::new("1. e4 e5\n2. Ke3 *".as_bytes())
+            .next()
+            .unwrap()
+            .unwrap();
+
+        match game.replay() {
+            Err(Error::InvalidPgn { line, column, .. }) => assert_eq!((line, column), (2, 4)),
+            _ => panic!("expected the illegal move to be reported"),
+        }
+    }
+
+    #[test]
+    fn recovers_after_a_bad_game() {
+        let pgn = "[Event \"bad\"]\n\n1. e4 @@ *\n\n[Event \"good\"]\n\n1. d4 *\n";
+        let mut reader = PgnReader::new(pgn.as_bytes());
+
+        assert!(reader.next().unwrap().is_err());
+        assert_eq!(
+            reader.next().unwrap().unwrap().header("Event"),
+            Some("good")
+        );
+        assert!(reader.next().is_none());
+
+        // the tags after a bad one still belong to the bad game
+        let pgn =
+            "[Event \"bad\"]\n[Site @]\n[Date \"?\"]\n\n1. e4 *\n\n[Event \"good\"]\n\n1. d4 *\n";
+        let mut reader = PgnReader::new(pgn.as_bytes());
+
+        assert!(reader.next().unwrap().is_err());
+        assert_eq!(
+            reader.next().unwrap().unwrap().header("Event"),
+            Some("good")
+        );
+        assert!(reader.next().is_none());
+
+        // without a termination token the blank line before the next tags ends the game
+        let pgn = "[Event \"bad\"]\n[Site @]\n\n1. e4 e5\n2. Nf3\n\n[Event \"good\"]\n\n1. d4 *\n";
+        let mut reader = PgnReader::new(pgn.as_bytes());
+
+        assert!(reader.next().unwrap().is_err());
+        assert_eq!(
+            reader.next().unwrap().unwrap().header("Event"),
+            Some("good")
+        );
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn export_then_import() {
+        let mut chess = Chess::new();
+        chess
+            .load_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 12")
+            .unwrap();
+
+        for m in ["O-O", "O-O-O", "Rf2", "Rd8+"] {
+            chess.play_san(m).unwrap();
+        }
+
+        let game = PgnReader::new(chess.to_pgn().as_bytes())
+            .next()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(game.replay().unwrap().get_fen(), chess.get_fen());
+    }
+
+    #[test]
+    fn result_ends_replay() {
+        let mut chess = Chess::new();
+        chess
+            .load_pgn("[Result \"1-0\"]\n\n1. e4 e5 1-0\n")
+            .unwrap();
+
+        assert_eq!(
+            chess.outcome(),
+            Some(Outcome::win(Color::WHITE, Termination::Resignation))
+        );
+        assert!(chess.to_pgn().ends_with("1. e4 e5 1-0\n"));
+        assert!(matches!(chess.play_san("Nf3"), Err(Error::GameOver)));
+
+        chess.load_pgn("1. e4 e5 1/2-1/2\n").unwrap();
+        assert_eq!(chess.outcome(), Some(Outcome::draw(Termination::Agreement)));
+
+        // the position's own outcome wins over the token
+        chess.load_pgn("1. f3 e5 2. g4 Qh4# 1/2-1/2\n").unwrap();
+        assert_eq!(
+            chess.outcome(),
+            Some(Outcome::win(Color::BLACK, Termination::Checkmate))
+        );
+
+        chess.load_pgn("1. e4 e5 *\n").unwrap();
+        assert_eq!(chess.outcome(), None);
+    }
+}
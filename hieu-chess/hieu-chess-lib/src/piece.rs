@@ -1,7 +1,6 @@
 use crate::chess::Color;
 use crate::error::Error;
 use serde::{Deserialize, Serialize};
-use std::convert::Into;
 
 #[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 #[repr(u8)]
@@ -51,11 +50,11 @@ impl TryFrom<&str> for Piece {
     }
 }
 
-impl Into<String> for Piece {
-    fn into(self) -> String {
+impl From<Piece> for String {
+    fn from(val: Piece) -> Self {
         use PType::*;
 
-        let t = match self.p_type {
+        let t = match val.p_type {
             PAWN => "p",
             KNIGHT => "n",
             BISHOP => "b",
@@ -64,7 +63,7 @@ impl Into<String> for Piece {
             KING => "k",
         };
 
-        if self.color == Color::WHITE {
+        if val.color == Color::WHITE {
             t.to_uppercase()
         } else {
             t.to_string()
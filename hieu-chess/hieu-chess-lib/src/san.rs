@@ -0,0 +1,408 @@
+use crate::chess::{GameState, Move};
+use crate::error::Error;
+use crate::move_gen::MoveGen;
+use crate::piece::PType;
+use crate::square::Square;
+
+// https://en.wikipedia.org/wiki/Algebraic_notation_(chess)
+pub fn move_to_san(state: &GameState, m: &Move) -> Result<String, Error> {
+    let Some(piece) = state.board.get(&m.from) else {
+        return Err(Error::UnknownMove);
+    };
+
+    if piece.color != state.side_to_move {
+        return Err(Error::MustWaitForTurn);
+    }
+
+    let legal_moves = MoveGen::moves(state, state.side_to_move);
+
+    if !legal_moves.contains(m) {
+        return Err(Error::IllegalMove);
+    }
+
+    let mut san = String::new();
+
+    if piece.p_type == PType::KING && state.is_castling(m) {
+        if m.to.file() > m.from.file() {
+            san.push_str("O-O");
+        } else {
+            san.push_str("O-O-O");
+        }
+    } else {
+        let is_capture = state.board.get(&m.to).is_some()
+            || (piece.p_type == PType::PAWN && state.en_passant_sq == Some(m.to));
+
+        if piece.p_type == PType::PAWN {
+            if is_capture {
+                san.push(file_char(m.from.file()));
+            }
+        } else {
+            san.push(piece_char(piece.p_type));
+
+            // other pieces of the same type that can also reach the target square
+            let ambiguous: Vec<&Move> = legal_moves
+                .iter()
+                .filter(|other| {
+                    other.to == m.to
+                        && other.from != m.from
+                        && state.board.get(&other.from).map(|p| p.p_type) == Some(piece.p_type)
+                })
+                .collect();
+
+            if !ambiguous.is_empty() {
+                let same_file = ambiguous.iter().any(|o| o.from.file() == m.from.file());
+                let same_rank = ambiguous.iter().any(|o| o.from.rank() == m.from.rank());
+
+                if !same_file {
+                    san.push(file_char(m.from.file()));
+                } else if !same_rank {
+                    san.push(rank_char(m.from.rank()));
+                } else {
+                    san.push_str(&m.from.get_notation());
+                }
+            }
+        }
+
+        if is_capture {
+            san.push('x');
+        }
+
+        san.push_str(&m.to.get_notation());
+
+        if let Some(ref promotion_piece) = m.promotion_piece {
+            san.push('=');
+            san.push(piece_char(promotion_piece.p_type));
+        }
+    }
+
+    // the check suffix needs the move played, so do it on a scratch copy
+    let mut scratch = state.clone();
+    scratch.play_move(m.clone())?;
+
+    if scratch.is_checkmate() {
+        san.push('#');
+    } else if scratch.is_in_check {
+        san.push('+');
+    }
+
+    Ok(san)
+}
+
+// resolves a SAN string against the legal moves of the side to move. common variants such as
+// `0-0`, a missing `+`/`#`, `e8Q` instead of `e8=Q` and lowercase promotion pieces are accepted
+pub fn parse_san(state: &GameState, san: &str) -> Result<Move, Error> {
+    let san = san
+        .trim()
+        .trim_end_matches(['+', '#', '!', '?'])
+        .trim_end_matches("e.p.")
+        .trim_end();
+
+    let legal_moves = MoveGen::moves(state, state.side_to_move);
+
+    let castle_kingside = match san {
+        "O-O" | "0-0" => Some(true),
+        "O-O-O" | "0-0-0" => Some(false),
+        _ => None,
+    };
+
+    if let Some(kingside) = castle_kingside {
+        return legal_moves
+            .into_iter()
+            .find(|m| {
+                state.board.get(&m.from).map(|p| p.p_type) == Some(PType::KING)
+                    && state.is_castling(m)
+                    && (m.to.file() > m.from.file()) == kingside
+            })
+            .ok_or(Error::IllegalMove);
+    }
+
+    let mut chars: Vec<char> = san.chars().collect();
+
+    let p_type = match chars.first() {
+        Some(c) if c.is_ascii_uppercase() => {
+            let p_type = piece_type(*c).ok_or(Error::InvalidSan)?;
+            chars.remove(0);
+            p_type
+        }
+        Some(_) => PType::PAWN,
+        None => return Err(Error::InvalidSan),
+    };
+
+    // promotion piece, written either as `e8=Q` or `e8Q`
+    let mut promotion = None;
+    if chars.len() > 2 && chars[chars.len() - 1].is_ascii_alphabetic() {
+        let c = chars.pop().expect("length was checked above");
+        let p = piece_type(c.to_ascii_uppercase()).ok_or(Error::InvalidSan)?;
+
+        if p == PType::PAWN || p == PType::KING {
+            return Err(Error::InvalidSan);
+        }
+
+        if chars.last() == Some(&'=') {
+            chars.pop();
+        }
+
+        promotion = Some(p);
+    }
+
+    if chars.len() < 2 {
+        return Err(Error::InvalidSan);
+    }
+
+    let to_str: String = chars.split_off(chars.len() - 2).into_iter().collect();
+    let to = Square::try_from(to_str.as_str()).map_err(|_| Error::InvalidSan)?;
+
+    // whatever is left is the disambiguation, optionally followed by a capture sign
+    if matches!(chars.last(), Some('x') | Some('-')) {
+        chars.pop();
+    }
+
+    let mut from_file = None;
+    let mut from_rank = None;
+    for c in chars {
+        match c {
+            'a'..='h' if from_file.is_none() && from_rank.is_none() => {
+                from_file = Some(c as u8 - b'a');
+            }
+            '1'..='8' if from_rank.is_none() => {
+                from_rank = Some(c as u8 - b'1');
+            }
+            _ => return Err(Error::InvalidSan),
+        }
+    }
+
+    let mut candidates = legal_moves.into_iter().filter(|m| {
+        m.to == to
+            && state.board.get(&m.from).map(|p| p.p_type) == Some(p_type)
+            && m.promotion_piece.as_ref().map(|p| p.p_type) == promotion
+            && from_file.is_none_or(|f| m.from.file() == f)
+            && from_rank.is_none_or(|r| m.from.rank() == r)
+            && !(p_type == PType::KING && state.is_castling(m))
+    });
+
+    match (candidates.next(), candidates.next()) {
+        (Some(m), None) => Ok(m),
+        (Some(_), Some(_)) => Err(Error::AmbiguousSan),
+        (None, _) => Err(Error::IllegalMove),
+    }
+}
+
+fn piece_char(p_type: PType) -> char {
+    use PType::*;
+
+    match p_type {
+        PAWN => 'P',
+        KNIGHT => 'N',
+        BISHOP => 'B',
+        ROOK => 'R',
+        QUEEN => 'Q',
+        KING => 'K',
+    }
+}
+
+fn piece_type(c: char) -> Option<PType> {
+    use PType::*;
+
+    match c {
+        'P' => Some(PAWN),
+        'N' => Some(KNIGHT),
+        'B' => Some(BISHOP),
+        'R' => Some(ROOK),
+        'Q' => Some(QUEEN),
+        'K' => Some(KING),
+        _ => None,
+    }
+}
+
+fn file_char(file: u8) -> char {
+    (b'a' + file) as char
+}
+
+fn rank_char(rank: u8) -> char {
+    (b'1' + rank) as char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::Color;
+    use crate::piece::Piece;
+
+    fn san(fen: &str, from: &str, to: &str, promotion_piece: Option<Piece>) -> String {
+        let mut state = GameState::new();
+        state.load_fen(fen).unwrap();
+
+        move_to_san(&state, &Move::from_str(from, to, promotion_piece)).unwrap()
+    }
+
+    #[test]
+    fn pieces_and_pawns() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+        assert_eq!(san(start, "e2", "e4", None), "e4");
+        assert_eq!(san(start, "g1", "f3", None), "Nf3");
+        assert_eq!(
+            san("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1", "e4", "d5", None),
+            "exd5"
+        );
+    }
+
+    #[test]
+    fn disambiguation() {
+        let fen = "4k3/8/8/8/8/8/8/R5RK w - - 0 1";
+        assert_eq!(san(fen, "a1", "d1", None), "Rad1");
+        assert_eq!(san(fen, "g1", "d1", None), "Rgd1");
+
+        let fen = "7k/8/8/R7/8/8/8/R6K w - - 0 1";
+        assert_eq!(san(fen, "a1", "a3", None), "R1a3");
+        assert_eq!(san(fen, "a5", "a3", None), "R5a3");
+
+        let fen = "6k1/8/8/8/8/Q7/8/Q1Q4K w - - 0 1";
+        assert_eq!(san(fen, "a1", "b2", None), "Qa1b2");
+        assert_eq!(san(fen, "a3", "b2", None), "Q3b2");
+    }
+
+    #[test]
+    fn special_moves() {
+        assert_eq!(
+            san("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5", "d6", None),
+            "exd6"
+        );
+
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(san(fen, "e1", "g1", None), "O-O");
+        assert_eq!(san(fen, "e1", "c1", None), "O-O-O");
+
+        assert_eq!(
+            san(
+                "8/P7/8/8/8/8/8/k6K w - - 0 1",
+                "a7",
+                "a8",
+                Some(Piece::new(PType::QUEEN, Color::WHITE))
+            ),
+            "a8=Q+"
+        );
+        assert_eq!(
+            san("6k1/5ppp/8/8/8/8/8/R6K w - - 0 1", "a1", "a8", None),
+            "Ra8#"
+        );
+    }
+
+    #[test]
+    fn illegal_moves() {
+        let mut state = GameState::new();
+        state
+            .load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
+            .unwrap();
+
+        assert!(move_to_san(&state, &Move::from_str("e2", "e5", None)).is_err());
+        assert!(move_to_san(&state, &Move::from_str("e7", "e5", None)).is_err());
+        assert!(move_to_san(&state, &Move::from_str("e4", "e5", None)).is_err());
+    }
+
+    fn parse(fen: &str, san: &str) -> Result<(String, String, Option<PType>), Error> {
+        let mut state = GameState::new();
+        state.load_fen(fen).unwrap();
+
+        parse_san(&state, san).map(|m| {
+            (
+                m.from.get_notation(),
+                m.to.get_notation(),
+                m.promotion_piece.map(|p| p.p_type),
+            )
+        })
+    }
+
+    fn from_to(from: &str, to: &str) -> Result<(String, String, Option<PType>), Error> {
+        Ok((from.to_string(), to.to_string(), None))
+    }
+
+    #[test]
+    fn parse_moves() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(parse(start, "e4").unwrap(), from_to("e2", "e4").unwrap());
+        assert_eq!(parse(start, "Nf3").unwrap(), from_to("g1", "f3").unwrap());
+        assert_eq!(parse(start, "Ng1f3").unwrap(), from_to("g1", "f3").unwrap());
+
+        let fen = "rnbqkb1r/ppp2ppp/5n2/3pp3/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1";
+        assert_eq!(parse(fen, "Nbd7").unwrap(), from_to("b8", "d7").unwrap());
+        assert_eq!(parse(fen, "Nfd7").unwrap(), from_to("f6", "d7").unwrap());
+        assert_eq!(parse(fen, "N8d7").unwrap(), from_to("b8", "d7").unwrap());
+        assert_eq!(parse(fen, "Bd7").unwrap(), from_to("c8", "d7").unwrap());
+        assert!(matches!(parse(fen, "Nd7"), Err(Error::AmbiguousSan)));
+
+        let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+        assert_eq!(parse(fen, "exd6").unwrap(), from_to("e5", "d6").unwrap());
+        assert_eq!(
+            parse(fen, "exd6 e.p.").unwrap(),
+            from_to("e5", "d6").unwrap()
+        );
+
+        let fen = "4k3/8/8/8/8/8/8/R5RK w - - 0 1";
+        assert_eq!(parse(fen, "Rad1").unwrap(), from_to("a1", "d1").unwrap());
+        assert_eq!(parse(fen, "Rgd1+").unwrap(), from_to("g1", "d1").unwrap());
+    }
+
+    #[test]
+    fn parse_variants() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        for san in ["O-O", "0-0", "O-O+"] {
+            assert_eq!(parse(fen, san).unwrap(), from_to("e1", "g1").unwrap());
+        }
+        for san in ["O-O-O", "0-0-0"] {
+            assert_eq!(parse(fen, san).unwrap(), from_to("e1", "c1").unwrap());
+        }
+
+        let fen = "8/P7/8/8/8/8/8/k6K w - - 0 1";
+        for san in ["a8=Q+", "a8=Q", "a8Q", "a8q", "a8=q"] {
+            assert_eq!(
+                parse(fen, san).unwrap(),
+                ("a7".to_string(), "a8".to_string(), Some(PType::QUEEN))
+            );
+        }
+        assert_eq!(parse(fen, "a8=N").unwrap().2, Some(PType::KNIGHT));
+
+        assert_eq!(
+            parse("6k1/5ppp/8/8/8/8/8/R6K w - - 0 1", "Ra8#").unwrap(),
+            from_to("a1", "a8").unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert!(matches!(parse(start, "e5"), Err(Error::IllegalMove)));
+        assert!(matches!(parse(start, "O-O"), Err(Error::IllegalMove)));
+        assert!(matches!(parse(start, "Ke2"), Err(Error::IllegalMove)));
+        assert!(matches!(parse(start, ""), Err(Error::InvalidSan)));
+        assert!(matches!(parse(start, "Zf3"), Err(Error::InvalidSan)));
+        assert!(matches!(parse(start, "Nj3"), Err(Error::InvalidSan)));
+        assert!(matches!(parse(start, "e9"), Err(Error::InvalidSan)));
+
+        let fen = "4k3/8/8/8/8/8/8/R5RK w - - 0 1";
+        assert!(matches!(parse(fen, "Rd1"), Err(Error::AmbiguousSan)));
+
+        // promotions must name the piece
+        assert!(matches!(
+            parse("8/P7/8/8/8/8/8/k6K w - - 0 1", "a8"),
+            Err(Error::IllegalMove)
+        ));
+        assert!(matches!(
+            parse("8/P7/8/8/8/8/8/k6K w - - 0 1", "a8=K"),
+            Err(Error::InvalidSan)
+        ));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut state = GameState::new();
+        state
+            .load_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+
+        for m in MoveGen::moves(&state, Color::WHITE) {
+            let san = move_to_san(&state, &m).unwrap();
+            assert_eq!(parse_san(&state, &san).unwrap(), m, "{}", san);
+        }
+    }
+}
@@ -56,16 +56,16 @@ impl Square {
     pub fn add(&self, delta: i8) -> Result<Self, Error> {
         let new_idx = (self.0 as i16 + delta as i16) as u8;
 
-        Ok(new_idx.try_into()?)
+        new_idx.try_into()
     }
 
     pub fn file(&self) -> u8 {
-        return self.0 & 7;
+        self.0 & 7
     }
 
     // go from 0 to 7
     pub fn rank(&self) -> u8 {
-        return self.0 >> 4;
+        self.0 >> 4
     }
 
     pub fn get_notation(&self) -> String {
@@ -101,7 +101,7 @@ impl TryFrom<&str> for Square {
 
         let mut value = value.chars();
         let ascii_val = value.next().unwrap().to_ascii_lowercase() as u8;
-        let file: u8 = if ascii_val >= 97 && ascii_val <= 104 {
+        let file: u8 = if (97..=104).contains(&ascii_val) {
             ascii_val - 97
         } else {
             return Err(Error::InvalidSquareString);
@@ -111,7 +111,7 @@ impl TryFrom<&str> for Square {
             return Err(Error::InvalidSquareString);
         };
 
-        if rank <= 0 {
+        if !(1..=8).contains(&rank) {
             return Err(Error::InvalidSquareString);
         }
 
@@ -139,6 +139,16 @@ mod tests {
         assert_eq!(Square::new(Rank::Five, File::C).0, 0x42);
     }
 
+    #[test]
+    fn square_from_notation() {
+        assert_eq!(Square::try_from("a1").unwrap().0, 0x00);
+        assert_eq!(Square::try_from("h8").unwrap().0, 0x77);
+
+        assert!(Square::try_from("a9").is_err());
+        assert!(Square::try_from("a0").is_err());
+        assert!(Square::try_from("i1").is_err());
+    }
+
     #[test]
     fn square_from_arbitrary_idx() {
         assert!(Square::try_from(0x78).is_err());
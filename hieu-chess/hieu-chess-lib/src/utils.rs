@@ -10,10 +10,6 @@ pub fn is_valid_idx(idx: u8) -> Result<(), Error> {
     }
 }
 
-pub fn is_enemy(piece: &Piece, side_to_move: Color) -> bool {
-    !is_friendly(piece, side_to_move)
-}
-
 pub fn is_friendly(piece: &Piece, side_to_move: Color) -> bool {
     piece.color == side_to_move
 }
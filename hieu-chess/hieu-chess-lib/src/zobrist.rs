@@ -0,0 +1,69 @@
+use crate::chess::Color;
+use crate::piece::Piece;
+use crate::square::Square;
+
+// https://www.chessprogramming.org/Zobrist_Hashing
+// 12 piece kinds on 64 squares, 4 castling rights, 8 en passant files and the side to move
+const PIECE_KEYS: usize = 0;
+const CASTLING_KEYS: usize = 12 * 64;
+const EN_PASSANT_KEYS: usize = CASTLING_KEYS + 4;
+const SIDE_KEY: usize = EN_PASSANT_KEYS + 8;
+const KEY_COUNT: usize = SIDE_KEY + 1;
+
+// the keys are generated at compile time with a fixed seed, so hashes are stable across runs
+const KEYS: [u64; KEY_COUNT] = {
+    let mut keys = [0; KEY_COUNT];
+    let mut state: u64 = 0x05EE_D0FC_4E55;
+    let mut i = 0;
+
+    while i < KEY_COUNT {
+        // https://prng.di.unimi.it/splitmix64.c
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+
+    keys
+};
+
+pub fn piece(piece: &Piece, sq: Square) -> u64 {
+    let color_idx = match piece.color {
+        Color::WHITE => 0,
+        Color::BLACK => 1,
+    };
+    // piece types are single bits, so the bit position doubles as an index
+    let piece_idx = color_idx * 6 + (piece.p_type as u8).trailing_zeros() as usize;
+    let sq_idx = (sq.rank() * 8 + sq.file()) as usize;
+
+    KEYS[PIECE_KEYS + piece_idx * 64 + sq_idx]
+}
+
+// 0 = white kingside, 1 = white queenside, 2 = black kingside, 3 = black queenside
+pub fn castling(right: usize) -> u64 {
+    KEYS[CASTLING_KEYS + right]
+}
+
+pub fn en_passant(sq: Square) -> u64 {
+    KEYS[EN_PASSANT_KEYS + sq.file() as usize]
+}
+
+pub fn side() -> u64 {
+    KEYS[SIDE_KEY]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn keys_are_unique() {
+        let keys: HashSet<u64> = KEYS.iter().copied().collect();
+
+        assert_eq!(keys.len(), KEY_COUNT);
+        assert!(!keys.contains(&0));
+    }
+}
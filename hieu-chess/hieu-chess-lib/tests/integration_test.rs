@@ -0,0 +1,162 @@
+use hieu_chess::{Chess, PerftConfig};
+
+// https://www.chessprogramming.org/Perft_Results
+// followed by the usual edge cases for en passant, castling and promotion bugs
+struct Perft {
+    name: &'static str,
+    fen: &'static str,
+    // nodes[i] is the node count at depth i + 1
+    nodes: &'static [usize],
+}
+
+const PERFTS: &[Perft] = &[
+    Perft {
+        name: "start position",
+        fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        nodes: &[20, 400, 8902, 197281, 4865609],
+    },
+    Perft {
+        name: "kiwipete",
+        fen: "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        nodes: &[48, 2039, 97862, 4085603],
+    },
+    Perft {
+        name: "position 3",
+        fen: "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        nodes: &[14, 191, 2812, 43238, 674624],
+    },
+    Perft {
+        name: "position 4",
+        fen: "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        nodes: &[6, 264, 9467, 422333],
+    },
+    Perft {
+        name: "position 4 mirrored",
+        fen: "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+        nodes: &[6, 264, 9467, 422333],
+    },
+    Perft {
+        name: "position 5",
+        fen: "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        nodes: &[44, 1486, 62379, 2103487],
+    },
+    Perft {
+        name: "position 6",
+        fen: "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        nodes: &[46, 2079, 89890, 3894594],
+    },
+    Perft {
+        name: "illegal en passant, pinned on the rank",
+        fen: "3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1",
+        nodes: &[18, 92, 1670, 10138, 185429, 1134888],
+    },
+    Perft {
+        name: "illegal en passant, pinned on the diagonal",
+        fen: "8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1",
+        nodes: &[13, 102, 1266, 10276, 135655, 1015133],
+    },
+    Perft {
+        name: "en passant gives check",
+        fen: "8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1",
+        nodes: &[15, 126, 1928, 13931, 206379, 1440467],
+    },
+    Perft {
+        name: "short castling gives check",
+        fen: "5k2/8/8/8/8/8/8/4K2R w K - 0 1",
+        nodes: &[15, 66, 1198, 6399, 120330, 661072],
+    },
+    Perft {
+        name: "long castling gives check",
+        fen: "3k4/8/8/8/8/8/8/R3K3 w Q - 0 1",
+        nodes: &[16, 71, 1286, 7418, 141077, 803711],
+    },
+    Perft {
+        name: "castling rights",
+        fen: "r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1",
+        nodes: &[26, 1141, 27826, 1274206],
+    },
+    Perft {
+        name: "castling prevented",
+        fen: "r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1",
+        nodes: &[44, 1494, 50509, 1720476],
+    },
+    Perft {
+        name: "promote out of check",
+        fen: "2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1",
+        nodes: &[11, 133, 1442, 19174, 266199, 3821001],
+    },
+    Perft {
+        name: "discovered check",
+        fen: "8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1",
+        nodes: &[29, 165, 5160, 31961, 1004658],
+    },
+    Perft {
+        name: "promote to give check",
+        fen: "4k3/1P6/8/8/8/8/K7/8 w - - 0 1",
+        nodes: &[9, 40, 472, 2661, 38983, 217342],
+    },
+    Perft {
+        name: "underpromote to give check",
+        fen: "8/P1k5/K7/8/8/8/8/8 w - - 0 1",
+        nodes: &[6, 27, 273, 1329, 18135, 92683],
+    },
+    Perft {
+        name: "self stalemate",
+        fen: "K1k5/8/P7/8/8/8/8/8 w - - 0 1",
+        nodes: &[2, 6, 13, 63, 382, 2217],
+    },
+    Perft {
+        name: "stalemate and checkmate",
+        fen: "8/k1P5/8/1K6/8/8/8/8 w - - 0 1",
+        nodes: &[10, 25, 268, 926, 10857, 43261, 567584],
+    },
+    Perft {
+        name: "stalemate and checkmate 2",
+        fen: "8/8/2k5/5q2/5n2/8/5K2/8 b - - 0 1",
+        nodes: &[37, 183, 6559, 23527],
+    },
+];
+
+// deep enough for startpos at depth 5 and kiwipete at depth 4. the test profile is optimized,
+// see the workspace Cargo.toml
+const CI_MAX_NODES: usize = 5_000_000;
+
+// runs every position at the deepest depth whose node count fits in the budget
+fn run_perfts(max_nodes: usize, perft: fn(&mut Chess, u8) -> usize) {
+    for position in PERFTS {
+        let Some((depth, expected)) = position
+            .nodes
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, nodes)| **nodes <= max_nodes)
+        else {
+            continue;
+        };
+
+        let mut chess = Chess::new();
+        chess.load_fen(position.fen).unwrap();
+
+        assert_eq!(
+            perft(&mut chess, depth as u8 + 1),
+            *expected,
+            "{} at depth {}",
+            position.name,
+            depth + 1
+        );
+    }
+}
+
+#[test]
+fn perft() {
+    run_perfts(CI_MAX_NODES, Chess::perft);
+}
+
+// takes a while, run with `cargo test --release -- --ignored`
+#[test]
+#[ignore]
+fn perft_deep() {
+    run_perfts(usize::MAX, |chess, depth| {
+        chess.perft_with(depth, PerftConfig::default())
+    });
+}
@@ -12,20 +12,69 @@ pub struct Move {
     pub promotion_piece: Option<String>,
 }
 
+impl From<&hieu_chess::Move> for Move {
+    fn from(m: &hieu_chess::Move) -> Self {
+        Self {
+            from: m.from.get_notation(),
+            to: m.to.get_notation(),
+            promotion_piece: m.promotion_piece.clone().map(|p| p.into()),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Captures {
     pub w: Vec<Capture>,
     pub b: Vec<Capture>,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct Outcome {
+    // "w", "b", or undefined for a draw
+    pub winner: Option<String>,
+    pub termination: String,
+}
+
+impl From<hieu_chess::Outcome> for Outcome {
+    fn from(outcome: hieu_chess::Outcome) -> Self {
+        Self {
+            winner: outcome.winner.map(|color| <&str>::from(color).to_string()),
+            termination: <&str>::from(outcome.termination).to_string(),
+        }
+    }
+}
+
 #[wasm_bindgen]
 pub struct ChessWasm {
     chess: Chess,
 }
 
+fn to_chess_move(m: JsValue) -> Result<hieu_chess::Move, JsError> {
+    let player_move: Move = serde_wasm_bindgen::from_value(m)?;
+
+    let promotion_piece: Option<Piece> = match player_move.promotion_piece {
+        Some(p) => Some(p.as_str().try_into()?),
+        None => None,
+    };
+
+    Ok(hieu_chess::Move::from_str(
+        &player_move.from,
+        &player_move.to,
+        promotion_piece,
+    ))
+}
+
+impl Default for ChessWasm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[wasm_bindgen]
 impl ChessWasm {
     pub fn new() -> Self {
+        utils::set_panic_hook();
+
         Self {
             chess: Chess::new(),
         }
@@ -36,24 +85,57 @@ impl ChessWasm {
     }
 
     pub fn play_move(&mut self, m: JsValue) -> Result<(), JsError> {
-        let player_move: Move = serde_wasm_bindgen::from_value(m).unwrap();
+        match self.chess.play_move(to_chess_move(m)?) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(JsError::new(&e.to_string())),
+        }
+    }
 
-        let promotion_piece: Option<Piece> = match player_move.promotion_piece {
-            Some(p) => Some(p.as_str().try_into()?),
-            None => None,
-        };
+    pub fn play_uci(&mut self, uci: &str) -> Result<(), JsError> {
+        let m = hieu_chess::Move::from_uci(uci)?;
 
-        match self.chess.play_move(hieu_chess::Move::from_str(
-            &player_move.from,
-            &player_move.to,
-            promotion_piece,
-        )) {
+        match self.chess.play_move(m) {
             Ok(_) => Ok(()),
             Err(e) => Err(JsError::new(&e.to_string())),
         }
     }
 
-    pub fn moves_for_square(&mut self, sq_str: String) -> Result<JsValue, JsError> {
+    // the move taken back, undefined at the start of the game
+    pub fn undo_move(&mut self) -> Result<JsValue, JsError> {
+        let m = self.chess.undo_move().as_ref().map(Move::from);
+
+        Ok(serde_wasm_bindgen::to_value(&m)?)
+    }
+
+    // the move played again, undefined at the end of the line
+    pub fn redo_move(&mut self) -> Result<JsValue, JsError> {
+        let m = self.chess.redo_move().as_ref().map(Move::from);
+
+        Ok(serde_wasm_bindgen::to_value(&m)?)
+    }
+
+    pub fn history(&self) -> Result<JsValue, JsError> {
+        let moves: Vec<Move> = self.chess.history().iter().map(Move::from).collect();
+
+        Ok(serde_wasm_bindgen::to_value(&moves)?)
+    }
+
+    pub fn ply(&self) -> usize {
+        self.chess.ply()
+    }
+
+    pub fn go_to_ply(&mut self, ply: usize) -> Result<(), JsError> {
+        Ok(self.chess.go_to_ply(ply)?)
+    }
+
+    pub fn move_to_san(&self, m: JsValue) -> Result<String, JsError> {
+        match self.chess.move_to_san(&to_chess_move(m)?) {
+            Ok(san) => Ok(san),
+            Err(e) => Err(JsError::new(&e.to_string())),
+        }
+    }
+
+    pub fn moves_for_square(&self, sq_str: String) -> Result<JsValue, JsError> {
         let square: Square = match sq_str.as_str().try_into() {
             Ok(sq) => sq,
             Err(e) => return Err(JsError::new(&e.to_string())),
@@ -63,14 +145,7 @@ impl ChessWasm {
             .chess
             .moves_for_square(square)
             .iter()
-            .map(|m| Move {
-                from: m.from.get_notation(),
-                to: m.to.get_notation(),
-                promotion_piece: match &m.promotion_piece {
-                    Some(p) => Some(p.clone().into()),
-                    None => None,
-                },
-            })
+            .map(Move::from)
             .collect();
 
         Ok(serde_wasm_bindgen::to_value(&moves)?)
@@ -91,30 +166,87 @@ impl ChessWasm {
         self.chess.reset();
     }
 
-    pub fn is_checkmate(&mut self) -> bool {
+    // undefined while the game is still going
+    pub fn outcome(&self) -> Result<JsValue, JsError> {
+        let outcome = self.chess.outcome().map(Outcome::from);
+
+        Ok(serde_wasm_bindgen::to_value(&outcome)?)
+    }
+
+    pub fn can_checkmate(&self, color: &str) -> Result<bool, JsError> {
+        Ok(self.chess.can_checkmate(color.try_into()?))
+    }
+
+    // the result if flagged ran out of time, without ending the game
+    pub fn adjudicate_timeout(&self, flagged: &str) -> Result<JsValue, JsError> {
+        let outcome = Outcome::from(self.chess.adjudicate_timeout(flagged.try_into()?));
+
+        Ok(serde_wasm_bindgen::to_value(&outcome)?)
+    }
+
+    pub fn resign(&mut self, color: &str) -> Result<(), JsError> {
+        Ok(self.chess.resign(color.try_into()?)?)
+    }
+
+    pub fn timeout(&mut self, color: &str) -> Result<(), JsError> {
+        Ok(self.chess.timeout(color.try_into()?)?)
+    }
+
+    pub fn agree_draw(&mut self) -> Result<(), JsError> {
+        Ok(self.chess.agree_draw()?)
+    }
+
+    // the draw the side to move could claim right now, undefined if there is none
+    pub fn claimable_draw(&self) -> Option<String> {
+        self.chess
+            .claimable_draw()
+            .map(|termination| <&str>::from(termination).to_string())
+    }
+
+    pub fn claim_draw(&mut self) -> Result<(), JsError> {
+        Ok(self.chess.claim_draw()?)
+    }
+
+    pub fn is_checkmate(&self) -> bool {
         self.chess.is_checkmate()
     }
 
-    pub fn is_draw(&mut self) -> bool {
+    pub fn is_draw(&self) -> bool {
         self.chess.is_draw()
     }
 
-    pub fn is_stalemate(&mut self) -> bool {
+    pub fn is_stalemate(&self) -> bool {
         self.chess.is_stalemate()
     }
 
-    pub fn is_insufficient_materials(&mut self) -> bool {
+    pub fn is_insufficient_materials(&self) -> bool {
         self.chess.is_insufficient_material()
     }
 
-    pub fn is_threefold_repetition(&mut self) -> bool {
+    pub fn is_dead_position(&self) -> bool {
+        self.chess.is_dead_position()
+    }
+
+    pub fn is_threefold_repetition(&self) -> bool {
         self.chess.is_threefold_repetition()
     }
 
+    pub fn is_fivefold_repetition(&self) -> bool {
+        self.chess.is_fivefold_repetition()
+    }
+
+    pub fn is_50_moves(&self) -> bool {
+        self.chess.is_50_moves()
+    }
+
+    pub fn is_75_moves(&self) -> bool {
+        self.chess.is_75_moves()
+    }
+
     pub fn load_fen(&mut self, fen: String) -> Result<(), JsError> {
         match self.chess.load_fen(&fen) {
             Ok(_) => Ok(()),
-            Err(_) => Err(JsError::new("Failed to load FEN")),
+            Err(e) => Err(JsError::new(&e.to_string())),
         }
     }
 
@@ -122,12 +254,29 @@ impl ChessWasm {
         self.chess.get_fen()
     }
 
+    pub fn load_pgn(&mut self, pgn: &str) -> Result<(), JsError> {
+        match self.chess.load_pgn(pgn) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(JsError::new(&e.to_string())),
+        }
+    }
+
+    pub fn to_pgn(&self) -> String {
+        self.chess.to_pgn()
+    }
+
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        self.chess.set_header(name, value);
+    }
+
     pub fn turn(&self) -> String {
         let color: &str = self.chess.get_turn().into();
         color.to_string()
     }
 
     pub fn set_turn(&mut self, turn: &str) -> Result<(), JsError> {
-        Ok(self.chess.set_turn(turn.try_into()?))
+        self.chess.set_turn(turn.try_into()?);
+
+        Ok(())
     }
 }
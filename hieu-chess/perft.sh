@@ -1 +1 @@
-target/debug/hieu-chess "$1" "$2" "$3" 
\ No newline at end of file
+target/debug/hieu-chess perft "$1" "$2" $3